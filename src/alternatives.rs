@@ -0,0 +1,215 @@
+use crate::cost::Cost;
+use crate::limits::SearchLimits;
+use crate::position::Position;
+use crate::search::{Search, SearchSpace};
+use crate::{Grid, Heuristic, SolveResult};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alternative<C = usize> {
+    // from the start to the goal, both included
+    pub path: Vec<Position>,
+    pub cost: C,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlternativeSettings {
+    // how many routes to return at most
+    pub count: usize,
+    // the largest fraction of a route's nodes it may share with any route
+    // found before it
+    pub max_overlap: f32,
+    // multiplies the cost of stepping onto a node each time a route uses it
+    pub penalty: usize,
+    // how many penalised searches to run before settling for fewer routes
+    pub max_attempts: usize,
+}
+
+impl Default for AlternativeSettings {
+    fn default() -> Self {
+        AlternativeSettings {
+            count: 3,
+            max_overlap: 0.5,
+            penalty: 2,
+            max_attempts: 12,
+        }
+    }
+}
+
+// what a search may not use, and how much more some nodes cost
+#[derive(Default)]
+struct Restrictions {
+    nodes: HashSet<Position>,
+    steps: HashSet<(Position, Position)>,
+    penalties: HashMap<Position, usize>,
+}
+
+// the grid as seen by a search that must respect some restrictions
+struct Restricted<'a, C> {
+    grid: &'a Grid<C>,
+    restrictions: &'a Restrictions,
+}
+
+impl<C: Cost> SearchSpace<C> for Restricted<'_, C> {
+    fn successors(&self, pos: &Position) -> Vec<(Position, C)> {
+        let restrictions = self.restrictions;
+        self.grid
+            .get_move_costs(pos)
+            .into_iter()
+            .filter(|(next, _)| {
+                !restrictions.nodes.contains(next)
+                    && !restrictions.steps.contains(&(pos.clone(), next.clone()))
+            })
+            .map(|(next, step)| {
+                let penalty = restrictions.penalties.get(&next).copied().unwrap_or(1);
+                (next, step.times(penalty))
+            })
+            .filter(|(_, step)| *step != C::infinity())
+            .collect()
+    }
+}
+
+// the fraction of `path`'s nodes that are also on `other`
+fn overlap(path: &[Position], other: &[Position]) -> f32 {
+    let other: HashSet<&Position> = other.iter().collect();
+    let shared = path.iter().filter(|pos| other.contains(pos)).count();
+    shared as f32 / path.len() as f32
+}
+
+impl<C: Cost> Grid<C> {
+    // A* from `from` to `to` that respects `restrictions`, leaving the nodes untouched
+    fn restricted_path(
+        &self,
+        from: &Position,
+        to: &Position,
+        heuristic: &Heuristic,
+        restrictions: &Restrictions,
+    ) -> Option<Alternative<C>> {
+        let heuristic = self.heuristic_function(heuristic);
+        let space = Restricted {
+            grid: self,
+            restrictions,
+        };
+        let mut search = Search::new(&space, from.clone(), to.clone(), heuristic);
+        if search.run(&SearchLimits::default()) != SolveResult::Found {
+            return None;
+        }
+        Some(Alternative {
+            path: search.path_to(to),
+            cost: search.goal_cost(),
+        })
+    }
+
+    // the unpenalised cost of walking a path
+    fn path_cost(&self, path: &[Position]) -> C {
+        path.windows(2).fold(C::zero(), |cost, pair| {
+            let step = self
+                .get_move_costs(&pair[0])
+                .into_iter()
+                .find(|(pos, _)| *pos == pair[1])
+                .map(|(_, step)| step);
+            cost.saturating_add(step.expect("path only makes valid moves"))
+        })
+    }
+
+    fn endpoints(&self) -> Option<(Position, Position)> {
+        let (start, goal) = (self.start.clone()?, self.goal.clone()?);
+        let usable = |pos: &Position| !self.is_blocked(pos);
+        (usable(&start) && usable(&goal)).then_some((start, goal))
+    }
+
+    // the `k` cheapest paths from the start to the goal that never visit a node
+    // twice, cheapest first (Yen's algorithm). fewer when there aren't that many
+    pub fn k_shortest_paths(&self, k: usize, heuristic: &Heuristic) -> Vec<Alternative<C>> {
+        let Some((start, goal)) = self.endpoints() else {
+            return Vec::new();
+        };
+        let no_restrictions = Restrictions::default();
+        let Some(shortest) = self.restricted_path(&start, &goal, heuristic, &no_restrictions)
+        else {
+            return Vec::new();
+        };
+        let mut found = vec![shortest];
+        let mut candidates: Vec<Alternative<C>> = Vec::new();
+        while found.len() < k {
+            let previous = &found[found.len() - 1].path;
+            // branch off the previous path at each of its nodes in turn
+            for spur in 0..previous.len() - 1 {
+                let root = &previous[..=spur];
+                let mut restrictions = Restrictions::default();
+                // the paths sharing this root already left it in these directions
+                for alternative in &found {
+                    if alternative.path.len() > spur + 1 && alternative.path[..=spur] == *root {
+                        let step = (root[spur].clone(), alternative.path[spur + 1].clone());
+                        restrictions.steps.insert(step);
+                    }
+                }
+                restrictions.nodes.extend(root[..spur].iter().cloned());
+                let Some(branch) =
+                    self.restricted_path(&root[spur], &goal, heuristic, &restrictions)
+                else {
+                    continue;
+                };
+                let mut path = root[..spur].to_vec();
+                path.extend(branch.path);
+                let cost = self.path_cost(&root[..=spur]) + branch.cost;
+                if !candidates.iter().any(|candidate| candidate.path == path) {
+                    candidates.push(Alternative { path, cost });
+                }
+            }
+            let cheapest = (0..candidates.len()).min_by_key(|&index| {
+                let candidate = &candidates[index];
+                (candidate.cost, candidate.path.len())
+            });
+            match cheapest {
+                Some(index) => found.push(candidates.swap_remove(index)),
+                None => break,
+            }
+        }
+        found.truncate(k);
+        found
+    }
+
+    // up to `settings.count` routes from the start to the goal that are quite
+    // different from each other. each search raises the cost of the nodes the
+    // routes so far used, and a route is only kept when it shares at most
+    // `max_overlap` of its nodes with every kept route. the shortest path comes first
+    pub fn alternative_routes(
+        &self,
+        settings: &AlternativeSettings,
+        heuristic: &Heuristic,
+    ) -> Vec<Alternative<C>> {
+        let Some((start, goal)) = self.endpoints() else {
+            return Vec::new();
+        };
+        let mut restrictions = Restrictions::default();
+        let mut routes: Vec<Alternative<C>> = Vec::new();
+        for _ in 0..settings.max_attempts {
+            if routes.len() >= settings.count {
+                break;
+            }
+            let Some(route) = self.restricted_path(&start, &goal, heuristic, &restrictions) else {
+                break;
+            };
+            let distinct = routes
+                .iter()
+                .all(|other| overlap(&route.path, &other.path) <= settings.max_overlap);
+            // the start and goal are on every route, so they are never penalised
+            let inner = route.path.len().saturating_sub(2);
+            for pos in route.path.iter().skip(1).take(inner) {
+                let penalty = restrictions.penalties.entry(pos.clone()).or_insert(1);
+                *penalty = penalty.saturating_mul(settings.penalty);
+            }
+            if distinct {
+                let cost = self.path_cost(&route.path);
+                routes.push(Alternative {
+                    path: route.path,
+                    cost,
+                });
+            }
+        }
+        routes
+    }
+}
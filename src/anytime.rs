@@ -0,0 +1,94 @@
+use crate::cost::Cost;
+use crate::limits::SearchLimits;
+use crate::position::Position;
+use crate::search::Search;
+use crate::{Agent, Grid, Heuristic, SolveResult};
+
+pub struct AnytimeSettings {
+    // weight on the heuristic for the first, fastest search
+    pub initial_epsilon: f64,
+    // how much the weight drops after each improved path
+    pub epsilon_step: f64,
+}
+
+impl Default for AnytimeSettings {
+    fn default() -> Self {
+        AnytimeSettings {
+            initial_epsilon: 3.0,
+            epsilon_step: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnytimeSolution<C = usize> {
+    // from the start to the goal, both included
+    pub path: Vec<Position>,
+    pub cost: C,
+    // the path costs at most `bound` times the optimal cost
+    pub bound: f64,
+}
+
+impl<C: Cost> Grid<C> {
+    // anytime repairing A*: finds a weighted path quickly, then keeps refining it
+    // with a falling epsilon until it is optimal or a limit is hit. every cheaper
+    // path is returned in order, the last one being the best
+    pub fn solve_anytime(
+        &self,
+        heuristic: &Heuristic,
+        settings: &AnytimeSettings,
+        limits: &SearchLimits,
+    ) -> Vec<AnytimeSolution<C>> {
+        let Some(start_pos) = self.start.clone() else {
+            panic!("no start position");
+        };
+        let Some(goal_pos) = self.goal.clone() else {
+            panic!("no goal position")
+        };
+        assert!(
+            settings.initial_epsilon >= 1.0,
+            "epsilon must be at least 1"
+        );
+        assert!(settings.epsilon_step > 0.0, "epsilon step must be positive");
+        if !self.is_connected(&start_pos, &goal_pos) {
+            return Vec::new();
+        }
+
+        let heuristic = self.heuristic_function(heuristic);
+        let mut epsilon = settings.initial_epsilon;
+        let agent = Agent {
+            grid: self,
+            size: 1,
+        };
+        let mut search =
+            Search::new(&agent, start_pos, goal_pos.clone(), heuristic).anytime(epsilon);
+        let mut solutions: Vec<AnytimeSolution<C>> = Vec::new();
+        while search.run(limits) == SolveResult::Found {
+            // the actual bound can be tighter than epsilon, using the smallest
+            // unweighted f cost among the nodes still waiting to be expanded
+            let cost = search.goal_cost();
+            let bound = match search.lower_bound() {
+                Some(lower) if lower > C::zero() => epsilon.min(cost.ratio(lower)).max(1.0),
+                _ => 1.0,
+            };
+            match solutions.last_mut() {
+                // a weight that finds no cheaper path can still prove a tighter bound
+                Some(last) if cost >= last.cost => last.bound = last.bound.min(bound),
+                _ => solutions.push(AnytimeSolution {
+                    path: search.path_to(&goal_pos),
+                    cost,
+                    bound,
+                }),
+            }
+            if bound <= 1.0 {
+                break;
+            }
+            // a step too small to change epsilon goes straight to the optimal search
+            let next = epsilon - settings.epsilon_step;
+            epsilon = if next < epsilon { next.max(1.0) } else { 1.0 };
+            search.reweight(epsilon);
+        }
+        solutions
+    }
+}
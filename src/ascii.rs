@@ -0,0 +1,121 @@
+use crate::cost::Cost;
+use crate::node::NodeType;
+use crate::position::Position;
+use crate::Grid;
+use std::fmt;
+use std::str::FromStr;
+
+// map legend
+const OBSTACLE: char = '#';
+const FLOOR: char = '.';
+const START: char = 'S';
+const GOAL: char = 'G';
+const PATH: char = '*';
+const TRAVERSED: char = 'o';
+
+#[derive(Debug, PartialEq)]
+pub enum ParseGridError {
+    Empty,
+    RaggedRow { row: usize },
+    UnknownChar { ch: char, x: usize, y: usize },
+    DuplicateStart,
+    DuplicateGoal,
+}
+
+impl fmt::Display for ParseGridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseGridError::Empty => write!(f, "map is empty"),
+            ParseGridError::RaggedRow { row } => {
+                write!(f, "row {row} has a different width to the first row")
+            }
+            ParseGridError::UnknownChar { ch, x, y } => {
+                write!(f, "unknown character {ch:?} at ({x}, {y})")
+            }
+            ParseGridError::DuplicateStart => write!(f, "map has more than one start"),
+            ParseGridError::DuplicateGoal => write!(f, "map has more than one goal"),
+        }
+    }
+}
+
+impl std::error::Error for ParseGridError {}
+
+// parses a character map, surrounding whitespace on each row is ignored
+// `#` wall, `.` floor, `S` start, `G` goal, `1`-`9` terrain cost
+impl FromStr for Grid {
+    type Err = ParseGridError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Grid::parse_with_cost_type(s)
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // like `str::parse`, measuring costs in `C`
+    pub fn parse_with_cost_type(s: &str) -> Result<Self, ParseGridError> {
+        let rows: Vec<Vec<char>> = s
+            .trim()
+            .lines()
+            .map(|line| line.trim().chars().collect())
+            .collect();
+        let width = rows.first().map_or(0, |row| row.len());
+        if width == 0 {
+            return Err(ParseGridError::Empty);
+        }
+        if let Some(row) = rows.iter().position(|row| row.len() != width) {
+            return Err(ParseGridError::RaggedRow { row });
+        }
+
+        let mut grid = Grid::with_cost_type(rows.len(), width);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &ch) in row.iter().enumerate() {
+                match ch {
+                    OBSTACLE => grid.set_obstacle(x, y),
+                    FLOOR => {}
+                    START if grid.start.is_some() => return Err(ParseGridError::DuplicateStart),
+                    START => grid.set_start(x, y),
+                    GOAL if grid.goal.is_some() => return Err(ParseGridError::DuplicateGoal),
+                    GOAL => grid.set_goal(x, y),
+                    '1'..='9' => {
+                        let cost = ch.to_digit(10).expect("is a digit") as usize;
+                        grid.set_terrain_cost(x, y, cost)
+                    }
+                    _ => return Err(ParseGridError::UnknownChar { ch, x, y }),
+                }
+            }
+        }
+        Ok(grid)
+    }
+}
+
+// renders the grid using the same legend as parsing, plus `*` for the path
+// and `o` for traversed nodes. terrain costs above 9 are shown as `9`
+impl<C: Cost> fmt::Display for Grid<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pos = Position::new(x as i32, y as i32);
+                let node = self.nodes.get(&pos).expect("node should exist").borrow();
+                let ch = if Some(&pos) == self.start.as_ref() {
+                    START
+                } else if Some(&pos) == self.goal.as_ref() {
+                    GOAL
+                } else {
+                    match node.node_type {
+                        NodeType::Obstacle => OBSTACLE,
+                        NodeType::Path => PATH,
+                        NodeType::Traversed => TRAVERSED,
+                        NodeType::Traversable if node.terrain_cost > 1 => {
+                            char::from_digit(node.terrain_cost.min(9) as u32, 10)
+                                .expect("is a digit")
+                        }
+                        NodeType::Traversable => FLOOR,
+                    }
+                };
+                write!(f, "{ch}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use crate::cost::Cost;
+use crate::node::NodeType;
+use crate::position::Position;
+use crate::Grid;
+use image::{DynamicImage, ImageError, Pixel, Rgb, RgbImage};
+use std::fmt;
+use std::path::Path;
+
+// marker colours, matching the colours used by the GUI
+pub const START_COLOR: Rgb<u8> = Rgb([0, 0, 255]);
+pub const GOAL_COLOR: Rgb<u8> = Rgb([0, 255, 0]);
+pub const PATH_COLOR: Rgb<u8> = Rgb([255, 0, 0]);
+
+const MAX_TERRAIN_COST: usize = 9;
+
+#[derive(Debug)]
+pub enum ImageMapError {
+    Image(ImageError),
+    Empty,
+    DuplicateStart,
+    DuplicateGoal,
+}
+
+impl fmt::Display for ImageMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageMapError::Image(err) => write!(f, "{err}"),
+            ImageMapError::Empty => write!(f, "image is empty"),
+            ImageMapError::DuplicateStart => write!(f, "image has more than one start pixel"),
+            ImageMapError::DuplicateGoal => write!(f, "image has more than one goal pixel"),
+        }
+    }
+}
+
+impl std::error::Error for ImageMapError {}
+
+impl From<ImageError> for ImageMapError {
+    fn from(err: ImageError) -> Self {
+        ImageMapError::Image(err)
+    }
+}
+
+pub struct ImageLegend {
+    // pixels darker than this are obstacles
+    pub obstacle_threshold: u8,
+    pub start_color: Rgb<u8>,
+    pub goal_color: Rgb<u8>,
+    // pixels of this colour are free to cross, so an exported solved map loads
+    // back without its path turning into walls. the terrain under the path is lost
+    pub path_color: Rgb<u8>,
+}
+
+impl Default for ImageLegend {
+    fn default() -> Self {
+        ImageLegend {
+            obstacle_threshold: 128,
+            start_color: START_COLOR,
+            goal_color: GOAL_COLOR,
+            path_color: PATH_COLOR,
+        }
+    }
+}
+
+impl ImageLegend {
+    // white is free to cross, darker shades cost up to 9 times as much
+    fn terrain_cost(&self, luma: u8) -> usize {
+        let range = (u8::MAX - self.obstacle_threshold) as usize;
+        if range == 0 {
+            return 1;
+        }
+        1 + ((u8::MAX - luma) as usize * (MAX_TERRAIN_COST - 1) + range / 2) / range
+    }
+}
+
+impl Grid {
+    // one pixel per node, grayscale and indexed images are expanded to rgb first
+    pub fn from_image(image: &DynamicImage, legend: &ImageLegend) -> Result<Self, ImageMapError> {
+        Grid::from_image_with_cost_type(image, legend)
+    }
+
+    pub fn load_image(path: impl AsRef<Path>) -> Result<Self, ImageMapError> {
+        Grid::load_image_with_cost_type(path)
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // like `from_image`, measuring costs in `C`
+    pub fn from_image_with_cost_type(
+        image: &DynamicImage,
+        legend: &ImageLegend,
+    ) -> Result<Self, ImageMapError> {
+        let image = image.to_rgb8();
+        if image.width() == 0 || image.height() == 0 {
+            return Err(ImageMapError::Empty);
+        }
+        let mut grid = Grid::with_cost_type(image.height() as usize, image.width() as usize);
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let (x, y) = (x as usize, y as usize);
+            if *pixel == legend.start_color {
+                if grid.start.is_some() {
+                    return Err(ImageMapError::DuplicateStart);
+                }
+                grid.set_start(x, y);
+            } else if *pixel == legend.goal_color {
+                if grid.goal.is_some() {
+                    return Err(ImageMapError::DuplicateGoal);
+                }
+                grid.set_goal(x, y);
+            } else if *pixel == legend.path_color {
+                continue;
+            } else {
+                let luma = pixel.to_luma()[0];
+                if luma < legend.obstacle_threshold {
+                    grid.set_obstacle(x, y);
+                } else {
+                    grid.set_terrain_cost(x, y, legend.terrain_cost(luma));
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn load_image_with_cost_type(path: impl AsRef<Path>) -> Result<Self, ImageMapError> {
+        let image = image::open(path)?;
+        Grid::from_image_with_cost_type(&image, &ImageLegend::default())
+    }
+
+    // obstacles are black, terrain is shaded by cost and the path is drawn in
+    // the legend's path colour, which `from_image` reads back as open ground
+    pub fn to_image(&self) -> RgbImage {
+        let legend = ImageLegend::default();
+        let range = (u8::MAX - legend.obstacle_threshold) as usize;
+        RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let pos = Position::new(x as i32, y as i32);
+            if Some(&pos) == self.start.as_ref() {
+                return legend.start_color;
+            }
+            if Some(&pos) == self.goal.as_ref() {
+                return legend.goal_color;
+            }
+            let node = self.nodes.get(&pos).expect("node should exist").borrow();
+            match node.node_type {
+                NodeType::Obstacle => Rgb([0, 0, 0]),
+                NodeType::Path => legend.path_color,
+                NodeType::Traversable | NodeType::Traversed => {
+                    let cost = node.terrain_cost.min(MAX_TERRAIN_COST) - 1;
+                    let shade = u8::MAX - (cost * range / (MAX_TERRAIN_COST - 1)) as u8;
+                    Rgb([shade, shade, shade])
+                }
+            }
+        })
+    }
+
+    pub fn save_image(&self, path: impl AsRef<Path>) -> Result<(), ImageMapError> {
+        self.to_image().save(path)?;
+        Ok(())
+    }
+}
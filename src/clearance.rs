@@ -0,0 +1,59 @@
+use crate::cost::Cost;
+use crate::position::Position;
+use crate::Grid;
+
+impl<C: Cost> Grid<C> {
+    // true clearance (annotated A*): the side of the largest obstacle free square
+    // whose top left corner is this node, 0 for obstacles
+    fn build_clearance(&self) -> Vec<usize> {
+        let mut clearance = vec![0; self.width * self.height];
+        for y in (0..self.height).rev() {
+            for x in (0..self.width).rev() {
+                let index = self.width * y + x;
+                if self.is_obstacle(&Position::new(x as i32, y as i32)) {
+                    continue;
+                }
+                let at = |x: usize, y: usize| {
+                    if x < self.width && y < self.height {
+                        clearance[self.width * y + x]
+                    } else {
+                        0
+                    }
+                };
+                clearance[index] = 1 + at(x + 1, y).min(at(x, y + 1)).min(at(x + 1, y + 1));
+            }
+        }
+        clearance
+    }
+
+    fn clearance_of(&self, pos: &Position) -> usize {
+        if self.clearance.borrow().is_none() {
+            *self.clearance.borrow_mut() = Some(self.build_clearance());
+        }
+        let index = self.get_index_from_pos(pos);
+        self.clearance
+            .borrow()
+            .as_ref()
+            .expect("clearance was just built")[index]
+    }
+
+    pub fn clearance_at(&self, x: usize, y: usize) -> usize {
+        let pos = Position::new(x as i32, y as i32);
+        assert!(self.is_valid_pos(&pos), "{:?} is invalid", pos);
+        self.clearance_of(&pos)
+    }
+
+    // whether an agent `agent_size` nodes wide can't stand with its top left
+    // corner here. the clearance map is shared by all agent sizes
+    pub(crate) fn is_blocked_for(&self, pos: &Position, agent_size: usize) -> bool {
+        if agent_size <= 1 {
+            return self.is_obstacle(pos);
+        }
+        self.clearance_of(pos) < agent_size
+    }
+
+    // the same for a single node agent
+    pub(crate) fn is_blocked(&self, pos: &Position) -> bool {
+        self.is_blocked_for(pos, 1)
+    }
+}
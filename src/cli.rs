@@ -0,0 +1,130 @@
+use path_finding::contraction::ContractionHierarchy;
+use path_finding::road::{GraphHeuristic, RoadGraph, Route};
+use std::io::BufRead;
+
+const USAGE: &str = "usage: route <graph.gr> [--co <graph.co>] [--heuristic zero|euclidean|great-circle] [--ch] [<from> <to>]
+--ch builds a contraction hierarchy first, which pays off over many queries.
+without <from> and <to>, queries are read from stdin as one `<from> <to>` pair per line.
+nodes use DIMACS numbering, starting at 1";
+
+struct RouteArgs {
+    gr: String,
+    co: Option<String>,
+    heuristic: GraphHeuristic,
+    contract: bool,
+    query: Option<(String, String)>,
+}
+
+fn parse_args(args: &[String]) -> Result<RouteArgs, String> {
+    let mut co = None;
+    let mut heuristic = None;
+    let mut contract = false;
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--co" => co = Some(args.next().ok_or("--co needs a file")?.clone()),
+            "--heuristic" => {
+                heuristic = Some(match args.next().map(String::as_str) {
+                    Some("zero") => GraphHeuristic::Zero,
+                    Some("euclidean") => GraphHeuristic::Euclidean,
+                    Some("great-circle") => GraphHeuristic::GreatCircle,
+                    _ => return Err("--heuristic must be zero, euclidean or great-circle".into()),
+                })
+            }
+            "--ch" => contract = true,
+            _ => positional.push(arg.clone()),
+        }
+    }
+    let mut positional = positional.into_iter();
+    let gr = positional.next().ok_or("missing graph file")?;
+    let query = match (positional.next(), positional.next(), positional.next()) {
+        (None, _, _) => None,
+        (Some(from), Some(to), None) => Some((from, to)),
+        _ => return Err("expected a <from> and a <to> node".into()),
+    };
+    // coordinates are longitude and latitude in DIMACS files
+    let heuristic = heuristic.unwrap_or(if co.is_some() {
+        GraphHeuristic::GreatCircle
+    } else {
+        GraphHeuristic::Zero
+    });
+    Ok(RouteArgs {
+        gr,
+        co,
+        heuristic,
+        contract,
+        query,
+    })
+}
+
+// answers one query, nodes are given and printed with DIMACS numbering
+fn answer(
+    graph: &RoadGraph,
+    solve: &dyn Fn(usize, usize) -> Option<Route>,
+    from: &str,
+    to: &str,
+) -> String {
+    let node = |id: &str| match id.parse::<usize>() {
+        Ok(id) if (1..=graph.node_count()).contains(&id) => Ok(id - 1),
+        _ => Err(format!("{id} is not a node")),
+    };
+    let (from, to) = match (node(from), node(to)) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(err), _) | (_, Err(err)) => return err,
+    };
+    match solve(from, to) {
+        Some(route) => {
+            let path: Vec<String> = route
+                .path
+                .iter()
+                .map(|node| (node + 1).to_string())
+                .collect();
+            format!(
+                "cost {} expanded {} path {}",
+                route.cost,
+                route.expanded,
+                path.join(" ")
+            )
+        }
+        None => "unreachable".to_string(),
+    }
+}
+
+// point to point queries on a DIMACS road graph, returns the exit code
+pub fn route(args: &[String]) -> i32 {
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            return 2;
+        }
+    };
+    let graph = match RoadGraph::load_dimacs(&args.gr, args.co.as_ref()) {
+        Ok(graph) => graph,
+        Err(err) => {
+            eprintln!("failed to load graph: {err}");
+            return 1;
+        }
+    };
+    let hierarchy = args.contract.then(|| ContractionHierarchy::build(&graph));
+    let solve = |from, to| match &hierarchy {
+        Some(hierarchy) => hierarchy.query(from, to),
+        None => graph.solve(from, to, args.heuristic),
+    };
+    if let Some((from, to)) = &args.query {
+        println!("{}", answer(&graph, &solve, from, to));
+        return 0;
+    }
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            return 1;
+        };
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [] => {}
+            [from, to] => println!("{}", answer(&graph, &solve, from, to)),
+            _ => println!("expected `<from> <to>`"),
+        }
+    }
+    0
+}
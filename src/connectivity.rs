@@ -0,0 +1,147 @@
+use crate::cost::Cost;
+use crate::node::NodeType;
+use crate::position::Position;
+use crate::Grid;
+
+// labels every traversable node with the id of its connected component.
+// ids are not dense, an edit hands out fresh ids instead of reusing old ones
+pub(crate) struct Components {
+    labels: Vec<Option<usize>>,
+    next_label: usize,
+    // the movement rules the labels were computed with
+    pub diagonal: bool,
+    pub agent_size: usize,
+}
+
+impl Components {
+    pub fn build<C: Cost>(grid: &Grid<C>, agent_size: usize) -> Self {
+        let mut components = Components {
+            labels: vec![None; grid.width * grid.height],
+            next_label: 0,
+            diagonal: grid.allow_diagonal,
+            agent_size,
+        };
+        for index in 0..components.labels.len() {
+            let pos = grid.get_pos_from_index(index);
+            if components.labels[index].is_none() && !grid.is_blocked_for(&pos, agent_size) {
+                components.flood(grid, pos);
+            }
+        }
+        components
+    }
+
+    pub fn label(&self, index: usize) -> Option<usize> {
+        self.labels[index]
+    }
+
+    // gives everything reachable from `from` a new label
+    fn flood<C: Cost>(&mut self, grid: &Grid<C>, from: Position) {
+        let label = self.next_label;
+        self.next_label += 1;
+        self.labels[grid.get_index_from_pos(&from)] = Some(label);
+        let mut stack = vec![from];
+        while let Some(pos) = stack.pop() {
+            for next in grid.get_passable_neighbours(&pos, self.agent_size) {
+                let index = grid.get_index_from_pos(&next);
+                if self.labels[index] != Some(label) {
+                    self.labels[index] = Some(label);
+                    stack.push(next);
+                }
+            }
+        }
+    }
+
+    // a new obstacle can split its component, so each side is relabelled.
+    // this only touches the component the obstacle was placed in
+    pub fn obstacle_added<C: Cost>(&mut self, grid: &Grid<C>, pos: &Position) {
+        let index = grid.get_index_from_pos(pos);
+        let Some(old_label) = self.labels[index].take() else {
+            return;
+        };
+        for next in grid.get_passable_neighbours(pos, self.agent_size) {
+            if self.labels[grid.get_index_from_pos(&next)] == Some(old_label) {
+                self.flood(grid, next);
+            }
+        }
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    pub(crate) fn is_obstacle(&self, pos: &Position) -> bool {
+        self.nodes
+            .get(pos)
+            .is_some_and(|node| node.borrow().node_type == NodeType::Obstacle)
+    }
+
+    // the neighbours a search for an agent `agent_size` nodes wide may step onto from `me`
+    pub(crate) fn get_passable_neighbours(
+        &self,
+        me: &Position,
+        agent_size: usize,
+    ) -> Vec<Position> {
+        let mut neighbours = self.get_neighbours(me);
+        if self.allow_diagonal {
+            neighbours.append(&mut self.get_neighbours_diag(me));
+        }
+        neighbours.retain(|pos| !self.is_blocked_for(pos, agent_size));
+        neighbours
+    }
+
+    // rebuilds the labels if they are missing or the movement rules changed.
+    // only one agent size is kept, asking for another relabels the grid
+    fn refresh_components(&self, agent_size: usize) {
+        let stale = self.components.borrow().as_ref().is_none_or(|components| {
+            components.diagonal != self.allow_diagonal || components.agent_size != agent_size
+        });
+        if stale {
+            *self.components.borrow_mut() = Some(Components::build(self, agent_size));
+        }
+    }
+
+    // id of the connected component containing the node, None for obstacles
+    pub fn component_at(&self, x: usize, y: usize) -> Option<usize> {
+        let pos = Position::new(x as i32, y as i32);
+        assert!(self.is_valid_pos(&pos), "{:?} is invalid", pos);
+        self.refresh_components(1);
+        let index = self.get_index_from_pos(&pos);
+        self.components
+            .borrow()
+            .as_ref()
+            .expect("components were just built")
+            .label(index)
+    }
+
+    pub(crate) fn is_connected(&self, from: &Position, to: &Position) -> bool {
+        self.is_connected_for(from, to, 1)
+    }
+
+    pub(crate) fn is_connected_for(
+        &self,
+        from: &Position,
+        to: &Position,
+        agent_size: usize,
+    ) -> bool {
+        // positions off the grid are connected to nothing
+        if !self.is_valid_pos(from) || !self.is_valid_pos(to) {
+            return false;
+        }
+        self.refresh_components(agent_size);
+        let components = self.components.borrow();
+        let components = components.as_ref().expect("components were just built");
+        let from = components.label(self.get_index_from_pos(from));
+        from.is_some() && from == components.label(self.get_index_from_pos(to))
+    }
+
+    // answers in constant time once the labels are built
+    pub fn is_goal_reachable(&self) -> bool {
+        self.is_goal_reachable_for_agent(1)
+    }
+
+    // whether an agent `agent_size` nodes wide can get from the start to the goal
+    pub fn is_goal_reachable_for_agent(&self, agent_size: usize) -> bool {
+        match (&self.start, &self.goal) {
+            (Some(start), Some(goal)) => self.is_connected_for(start, goal, agent_size),
+            _ => false,
+        }
+    }
+}
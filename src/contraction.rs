@@ -0,0 +1,490 @@
+use crate::cost::Cost;
+use crate::position::Position;
+use crate::road::{RoadGraph, Route};
+use crate::Grid;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// how many nodes a witness search may settle before it gives up and the
+// shortcut is added anyway. extra shortcuts cost memory but never correctness
+const WITNESS_SETTLE_LIMIT: usize = 64;
+
+#[derive(Debug, Clone)]
+struct Shortcut {
+    from: usize,
+    to: usize,
+    weight: u64,
+    // the two arcs this one replaces, None for arcs of the original graph
+    children: Option<(usize, usize)>,
+}
+
+// a distance and the arc it came through for every node, kept between searches.
+// only the nodes a search touched are reset, so searches cost what they visit
+// rather than the size of the graph
+#[derive(Debug, Clone)]
+struct Distances {
+    entries: Vec<(u64, Option<usize>)>,
+    touched: Vec<usize>,
+}
+
+impl Distances {
+    fn new(nodes: usize) -> Self {
+        Distances {
+            entries: vec![(u64::MAX, None); nodes],
+            touched: Vec::new(),
+        }
+    }
+
+    fn get(&self, node: usize) -> Option<u64> {
+        let (distance, _) = self.entries[node];
+        (distance != u64::MAX).then_some(distance)
+    }
+
+    fn arc(&self, node: usize) -> Option<usize> {
+        self.entries[node].1
+    }
+
+    fn set(&mut self, node: usize, distance: u64, arc: Option<usize>) {
+        if self.entries[node].0 == u64::MAX {
+            self.touched.push(node);
+        }
+        self.entries[node] = (distance, arc);
+    }
+
+    fn clear(&mut self) {
+        for node in self.touched.drain(..) {
+            self.entries[node] = (u64::MAX, None);
+        }
+    }
+}
+
+// an arc of the hierarchy as seen from one of its ends
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    node: usize,
+    weight: u64,
+    arc: usize,
+}
+
+// a road graph preprocessed by contracting its nodes one at a time, least
+// important first, adding shortcuts so distances between the remaining nodes
+// are kept. queries then only ever climb the hierarchy from both ends
+#[derive(Debug, Clone)]
+pub struct ContractionHierarchy {
+    arcs: Vec<Shortcut>,
+    // the order each node was contracted in
+    rank: Vec<usize>,
+    // arcs leaving each node towards a higher rank
+    up: Vec<Vec<Step>>,
+    // arcs entering each node from a higher rank
+    down: Vec<Vec<Step>>,
+}
+
+// scratch space for the forward and backward searches of a query. reusing
+// one across queries saves allocating a distance per node each time
+#[derive(Debug, Clone)]
+pub struct QueryContext {
+    searches: [Distances; 2],
+}
+
+// the graph while it is being contracted, only arcs between nodes that are
+// still there are listed
+struct Contraction {
+    arcs: Vec<Shortcut>,
+    outgoing: Vec<Vec<usize>>,
+    incoming: Vec<Vec<usize>>,
+    contracted: Vec<bool>,
+    // how many neighbours of each node are already contracted, which spreads
+    // the contraction evenly over the graph
+    contracted_neighbours: Vec<usize>,
+    witnesses: RefCell<Distances>,
+    // one more than the deepest contracted neighbour, keeps the hierarchy shallow
+    depth: Vec<usize>,
+}
+
+impl Contraction {
+    fn new(graph: &RoadGraph) -> Self {
+        let nodes = graph.node_count();
+        let mut contraction = Contraction {
+            arcs: Vec::new(),
+            outgoing: vec![Vec::new(); nodes],
+            incoming: vec![Vec::new(); nodes],
+            contracted: vec![false; nodes],
+            contracted_neighbours: vec![0; nodes],
+            witnesses: RefCell::new(Distances::new(nodes)),
+            depth: vec![0; nodes],
+        };
+        for from in 0..nodes {
+            for edge in graph.edges(from) {
+                if edge.to != from {
+                    contraction.add_arc(from, edge.to, edge.weight, None);
+                }
+            }
+        }
+        contraction
+    }
+
+    // keeps only the cheapest arc between two nodes, replaced arcs stay in
+    // `arcs` since shortcuts may still refer to them
+    fn add_arc(&mut self, from: usize, to: usize, weight: u64, children: Option<(usize, usize)>) {
+        let existing = self.outgoing[from]
+            .iter()
+            .position(|&arc| self.arcs[arc].to == to);
+        if let Some(position) = existing {
+            let old = self.outgoing[from][position];
+            if self.arcs[old].weight <= weight {
+                return;
+            }
+            self.outgoing[from].swap_remove(position);
+            self.incoming[to].retain(|&arc| arc != old);
+        }
+        let arc = self.arcs.len();
+        self.arcs.push(Shortcut {
+            from,
+            to,
+            weight,
+            children,
+        });
+        self.outgoing[from].push(arc);
+        self.incoming[to].push(arc);
+    }
+
+    // finds the cheapest distances from `source` to nodes within `limit` into
+    // `witnesses`, avoiding `skip` and anything contracted
+    fn witness_search(&self, source: usize, skip: usize, limit: u64) {
+        let mut distances = self.witnesses.borrow_mut();
+        distances.clear();
+        distances.set(source, 0, None);
+        let mut open_set = BinaryHeap::from([Reverse((0, source))]);
+        let mut settled = 0;
+        while let Some(Reverse((distance, node))) = open_set.pop() {
+            if distances.get(node).is_some_and(|best| distance > best) {
+                continue; // stale entry
+            }
+            settled += 1;
+            if distance > limit || settled > WITNESS_SETTLE_LIMIT {
+                break;
+            }
+            for &arc in &self.outgoing[node] {
+                let Shortcut { to, weight, .. } = self.arcs[arc];
+                if to == skip || self.contracted[to] {
+                    continue;
+                }
+                let next = distance + weight;
+                if distances.get(to).is_none_or(|best| next < best) {
+                    distances.set(to, next, None);
+                    open_set.push(Reverse((next, to)));
+                }
+            }
+        }
+    }
+
+    // the shortcuts contracting `node` needs as (from, to, weight, incoming arc, outgoing arc)
+    fn shortcuts(&self, node: usize) -> Vec<(usize, usize, u64, usize, usize)> {
+        let mut shortcuts = Vec::new();
+        for &incoming in &self.incoming[node] {
+            let from = self.arcs[incoming].from;
+            let limit = self.outgoing[node]
+                .iter()
+                .map(|&arc| self.arcs[incoming].weight + self.arcs[arc].weight)
+                .max()
+                .unwrap_or(0);
+            self.witness_search(from, node, limit);
+            let witnesses = self.witnesses.borrow();
+            for &outgoing in &self.outgoing[node] {
+                let to = self.arcs[outgoing].to;
+                let weight = self.arcs[incoming].weight + self.arcs[outgoing].weight;
+                if to == from || witnesses.get(to).is_some_and(|witness| witness <= weight) {
+                    continue;
+                }
+                shortcuts.push((from, to, weight, incoming, outgoing));
+            }
+        }
+        shortcuts
+    }
+
+    // smaller contracts sooner: the arcs added minus the arcs removed
+    fn priority(&self, node: usize) -> i64 {
+        let removed = self.incoming[node].len() + self.outgoing[node].len();
+        self.shortcuts(node).len() as i64 - removed as i64
+            + self.contracted_neighbours[node] as i64
+            + self.depth[node] as i64
+    }
+
+    fn contract(&mut self, node: usize) {
+        for (from, to, weight, incoming, outgoing) in self.shortcuts(node) {
+            self.add_arc(from, to, weight, Some((incoming, outgoing)));
+        }
+        self.contracted[node] = true;
+        let incoming = std::mem::take(&mut self.incoming[node]);
+        let outgoing = std::mem::take(&mut self.outgoing[node]);
+        for &arc in &incoming {
+            let from = self.arcs[arc].from;
+            self.outgoing[from].retain(|&other| other != arc);
+            self.contracted_neighbours[from] += 1;
+            self.depth[from] = self.depth[from].max(self.depth[node] + 1);
+        }
+        for &arc in &outgoing {
+            let to = self.arcs[arc].to;
+            self.incoming[to].retain(|&other| other != arc);
+            self.contracted_neighbours[to] += 1;
+            self.depth[to] = self.depth[to].max(self.depth[node] + 1);
+        }
+        // the hierarchy needs every arc a node had when it was contracted
+        self.incoming[node] = incoming;
+        self.outgoing[node] = outgoing;
+    }
+}
+
+// one direction of a query: the best distance to every node reached and the
+// arc it was reached through
+struct Search<'a> {
+    distances: &'a mut Distances,
+    open_set: BinaryHeap<Reverse<(u64, usize)>>,
+}
+
+impl<'a> Search<'a> {
+    fn new(distances: &'a mut Distances, source: usize) -> Self {
+        distances.clear();
+        distances.set(source, 0, None);
+        Search {
+            distances,
+            open_set: BinaryHeap::from([Reverse((0, source))]),
+        }
+    }
+
+    fn min_key(&self) -> u64 {
+        self.open_set
+            .peek()
+            .map_or(u64::MAX, |Reverse((key, _))| *key)
+    }
+
+    fn distance(&self, node: usize) -> Option<u64> {
+        self.distances.get(node)
+    }
+
+    // settles the closest node and relaxes its arcs in `climb`. `stall` has the
+    // arcs reaching it from higher ranks, when one of those is a shortcut to it
+    // the node can't be on a shortest path and isn't expanded (stall on demand)
+    fn step(&mut self, climb: &[Vec<Step>], stall: &[Vec<Step>]) -> Option<usize> {
+        let Reverse((distance, node)) = self.open_set.pop()?;
+        if self.distance(node).is_some_and(|best| distance > best) {
+            return None; // stale entry
+        }
+        let stalled = stall[node].iter().any(|step| {
+            self.distance(step.node)
+                .is_some_and(|above| above + step.weight < distance)
+        });
+        if stalled {
+            return Some(node);
+        }
+        for &Step {
+            node: next,
+            weight,
+            arc,
+        } in &climb[node]
+        {
+            let next_distance = distance + weight;
+            if self.distance(next).is_none_or(|best| next_distance < best) {
+                self.distances.set(next, next_distance, Some(arc));
+                self.open_set.push(Reverse((next_distance, next)));
+            }
+        }
+        Some(node)
+    }
+}
+
+impl ContractionHierarchy {
+    pub fn build(graph: &RoadGraph) -> Self {
+        let nodes = graph.node_count();
+        let mut contraction = Contraction::new(graph);
+        let mut queue: BinaryHeap<Reverse<(i64, usize)>> = (0..nodes)
+            .map(|node| Reverse((contraction.priority(node), node)))
+            .collect();
+        let mut rank = vec![0; nodes];
+        let mut next_rank = 0;
+        while let Some(Reverse((priority, node))) = queue.pop() {
+            if contraction.contracted[node] {
+                continue;
+            }
+            // priorities go stale as neighbours are contracted, so check again
+            // before committing and put it back if it is no longer the smallest
+            let current = contraction.priority(node);
+            if current > priority && queue.peek().is_some_and(|Reverse(next)| current > next.0) {
+                queue.push(Reverse((current, node)));
+                continue;
+            }
+            contraction.contract(node);
+            rank[node] = next_rank;
+            next_rank += 1;
+        }
+
+        let arcs = &contraction.arcs;
+        let up = (contraction.outgoing.iter())
+            .map(|outgoing| {
+                let step = |&arc: &usize| Step {
+                    node: arcs[arc].to,
+                    weight: arcs[arc].weight,
+                    arc,
+                };
+                outgoing.iter().map(step).collect()
+            })
+            .collect();
+        let down = (contraction.incoming.iter())
+            .map(|incoming| {
+                let step = |&arc: &usize| Step {
+                    node: arcs[arc].from,
+                    weight: arcs[arc].weight,
+                    arc,
+                };
+                incoming.iter().map(step).collect()
+            })
+            .collect();
+        ContractionHierarchy {
+            arcs: contraction.arcs,
+            rank,
+            up,
+            down,
+        }
+    }
+
+    // scratch space sized for this hierarchy, for `query_with`
+    pub fn context(&self) -> QueryContext {
+        let nodes = self.node_count();
+        QueryContext {
+            searches: [Distances::new(nodes), Distances::new(nodes)],
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.rank.len()
+    }
+
+    // how many arcs the hierarchy holds, shortcuts included
+    pub fn arc_count(&self) -> usize {
+        self.up.iter().chain(&self.down).map(Vec::len).sum()
+    }
+
+    pub fn rank(&self, node: usize) -> usize {
+        self.rank[node]
+    }
+
+    // bidirectional dijkstra where both searches only climb to higher ranks,
+    // the path is unpacked back into arcs of the original graph
+    pub fn query(&self, start: usize, goal: usize) -> Option<Route> {
+        self.query_with(&mut self.context(), start, goal)
+    }
+
+    // like `query`, with the scratch space of a context from `context`
+    pub fn query_with(
+        &self,
+        context: &mut QueryContext,
+        start: usize,
+        goal: usize,
+    ) -> Option<Route> {
+        assert!(
+            start < self.node_count(),
+            "node {start} is not in the graph"
+        );
+        assert!(goal < self.node_count(), "node {goal} is not in the graph");
+        assert!(
+            context.searches[0].entries.len() == self.node_count(),
+            "the context was made for another hierarchy"
+        );
+        let [forward, backward] = &mut context.searches;
+        let mut forward = Search::new(forward, start);
+        let mut backward = Search::new(backward, goal);
+        let mut best: Option<(u64, usize)> = (start == goal).then_some((0, start));
+        let mut expanded = 0;
+        loop {
+            let bound = best.map_or(u64::MAX, |(cost, _)| cost);
+            let forward_key = forward.min_key();
+            let backward_key = backward.min_key();
+            if forward_key.min(backward_key) >= bound {
+                break;
+            }
+            let (search, other, climb, stall) = if forward_key <= backward_key {
+                (&mut forward, &backward, &self.up, &self.down)
+            } else {
+                (&mut backward, &forward, &self.down, &self.up)
+            };
+            let Some(node) = search.step(climb, stall) else {
+                continue;
+            };
+            expanded += 1;
+            if let (Some(here), Some(there)) = (search.distance(node), other.distance(node)) {
+                if here + there < bound {
+                    best = Some((here + there, node));
+                }
+            }
+        }
+
+        let (cost, meeting) = best?;
+        let mut arcs = Vec::new();
+        let mut node = meeting;
+        while let Some(arc) = forward.distances.arc(node) {
+            arcs.push(arc);
+            node = self.arcs[arc].from;
+        }
+        arcs.reverse();
+        let mut node = meeting;
+        while let Some(arc) = backward.distances.arc(node) {
+            arcs.push(arc);
+            node = self.arcs[arc].to;
+        }
+        let mut path = vec![start];
+        for arc in arcs {
+            self.unpack(arc, &mut path);
+        }
+        Some(Route {
+            path,
+            cost,
+            expanded,
+        })
+    }
+
+    // appends the original nodes an arc stands for, without its first node
+    fn unpack(&self, arc: usize, path: &mut Vec<usize>) {
+        let mut stack = vec![arc];
+        while let Some(arc) = stack.pop() {
+            match self.arcs[arc].children {
+                Some((first, second)) => {
+                    stack.push(second);
+                    stack.push(first);
+                }
+                None => path.push(self.arcs[arc].to),
+            }
+        }
+    }
+}
+
+impl RoadGraph {
+    pub fn contraction_hierarchy(&self) -> ContractionHierarchy {
+        ContractionHierarchy::build(self)
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // the shortest path between two nodes over a hierarchy built from this
+    // grid's `road_graph`, both ends included. None when there is no path
+    pub fn hierarchy_path(
+        &self,
+        hierarchy: &ContractionHierarchy,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) -> Option<Vec<Position>> {
+        assert!(
+            hierarchy.node_count() == self.width * self.height,
+            "the hierarchy was built for another grid"
+        );
+        let index = |(x, y): (usize, usize)| {
+            let pos = Position::new(x as i32, y as i32);
+            assert!(self.is_valid_pos(&pos), "{:?} is invalid", pos);
+            self.get_index_from_pos(&pos)
+        };
+        let route = hierarchy.query(index(from), index(to))?;
+        let path = route.path.into_iter();
+        Some(path.map(|node| self.get_pos_from_index(node)).collect())
+    }
+}
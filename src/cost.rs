@@ -0,0 +1,229 @@
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::ops::{Add, Sub};
+
+// the numeric type path costs are measured in. integers keep the classic
+// scaling where a straight step costs 10 and a diagonal 14, fixed-point and
+// floats can price a diagonal at exactly (or very nearly) sqrt(2)
+pub trait Cost: Copy + Ord + Debug + Add<Output = Self> + Sub<Output = Self> + 'static {
+    fn zero() -> Self;
+    // stands in for "not reached yet", larger than any real path
+    fn infinity() -> Self;
+    fn straight() -> Self;
+    fn diagonal() -> Self;
+    // the step cost multiplied by a terrain cost, `infinity()` when the product
+    // doesn't fit. solvers never take an infinite step
+    fn times(self, terrain: usize) -> Self;
+    // the sum of two costs, `infinity()` when it doesn't fit. a path that
+    // costs more than the type can hold is treated as no path
+    fn saturating_add(self, other: Self) -> Self;
+    // a distance in nodes, rounded down so heuristics stay admissible
+    fn from_distance(distance: f64) -> Self;
+    // the cost multiplied by a real factor, rounded down. used to weight heuristics
+    fn scale(self, factor: f64) -> Self;
+    // how many times `other` goes into the cost
+    fn ratio(self, other: Self) -> f64;
+}
+
+macro_rules! integer_cost {
+    ($($int:ty),*) => {$(
+        impl Cost for $int {
+            fn zero() -> Self {
+                0
+            }
+
+            fn infinity() -> Self {
+                <$int>::MAX
+            }
+
+            fn straight() -> Self {
+                10
+            }
+
+            fn diagonal() -> Self {
+                14
+            }
+
+            fn times(self, terrain: usize) -> Self {
+                <$int>::try_from(terrain)
+                    .ok()
+                    .and_then(|terrain| self.checked_mul(terrain))
+                    .unwrap_or(<$int>::MAX)
+            }
+
+            fn saturating_add(self, other: Self) -> Self {
+                <$int>::saturating_add(self, other)
+            }
+
+            fn from_distance(distance: f64) -> Self {
+                (distance * 10.0) as $int
+            }
+
+            fn scale(self, factor: f64) -> Self {
+                (self as f64 * factor) as $int
+            }
+
+            fn ratio(self, other: Self) -> f64 {
+                self as f64 / other as f64
+            }
+        }
+    )*};
+}
+
+// u8 and i8 are left out, with a straight step of 10 they overflow after a
+// dozen or two steps. the signed types work even though costs are never negative
+integer_cost!(u16, u32, u64, u128, usize, i16, i32, i64, i128, isize);
+
+// unsigned fixed-point with 16 fractional bits, a straight step is 1.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fixed(pub u64);
+
+impl Fixed {
+    pub const FRACTION_BITS: u32 = 16;
+    pub const ONE: Fixed = Fixed(1 << Self::FRACTION_BITS);
+
+    pub fn from_f64(value: f64) -> Self {
+        Fixed((value * Self::ONE.0 as f64).round() as u64)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::ONE.0 as f64
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Fixed(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Fixed(self.0 - rhs.0)
+    }
+}
+
+impl Cost for Fixed {
+    fn zero() -> Self {
+        Fixed(0)
+    }
+
+    fn infinity() -> Self {
+        Fixed(u64::MAX)
+    }
+
+    fn straight() -> Self {
+        Fixed::ONE
+    }
+
+    fn diagonal() -> Self {
+        Fixed::from_f64(std::f64::consts::SQRT_2)
+    }
+
+    fn times(self, terrain: usize) -> Self {
+        u64::try_from(terrain)
+            .ok()
+            .and_then(|terrain| self.0.checked_mul(terrain))
+            .map_or(Self::infinity(), Fixed)
+    }
+
+    fn saturating_add(self, other: Self) -> Self {
+        Fixed(self.0.saturating_add(other.0))
+    }
+
+    fn from_distance(distance: f64) -> Self {
+        Fixed((distance * Self::ONE.0 as f64) as u64)
+    }
+
+    fn scale(self, factor: f64) -> Self {
+        Fixed((self.0 as f64 * factor) as u64)
+    }
+
+    fn ratio(self, other: Self) -> f64 {
+        self.0 as f64 / other.0 as f64
+    }
+}
+
+// an f64 ordered with `total_cmp`, a straight step is 1.0
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderedFloat(pub f64);
+
+impl PartialEq for OrderedFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedFloat {}
+
+impl PartialOrd for OrderedFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedFloat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Add for OrderedFloat {
+    type Output = OrderedFloat;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        OrderedFloat(self.0 + rhs.0)
+    }
+}
+
+impl Sub for OrderedFloat {
+    type Output = OrderedFloat;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        OrderedFloat(self.0 - rhs.0)
+    }
+}
+
+impl Cost for OrderedFloat {
+    fn zero() -> Self {
+        OrderedFloat(0.0)
+    }
+
+    fn infinity() -> Self {
+        OrderedFloat(f64::INFINITY)
+    }
+
+    fn straight() -> Self {
+        OrderedFloat(1.0)
+    }
+
+    fn diagonal() -> Self {
+        OrderedFloat(std::f64::consts::SQRT_2)
+    }
+
+    fn times(self, terrain: usize) -> Self {
+        OrderedFloat(self.0 * terrain as f64)
+    }
+
+    fn saturating_add(self, other: Self) -> Self {
+        self + other
+    }
+
+    fn from_distance(distance: f64) -> Self {
+        OrderedFloat(distance)
+    }
+
+    fn scale(self, factor: f64) -> Self {
+        OrderedFloat(self.0 * factor)
+    }
+
+    fn ratio(self, other: Self) -> f64 {
+        self.0 / other.0
+    }
+}
@@ -0,0 +1,131 @@
+use crate::cost::Cost;
+use crate::node::NodeType;
+use crate::position::Position;
+use crate::Grid;
+
+impl<C: Cost> Grid<C> {
+    fn checked_pos(&self, x: usize, y: usize) -> Position {
+        let pos = Position::new(x as i32, y as i32);
+        assert!(self.is_valid_pos(&pos), "{:?} is invalid", pos);
+        pos
+    }
+
+    // changes a node without touching the caches, callers must call `edited`
+    fn write_obstacle(&mut self, pos: &Position, obstacle: bool) {
+        let mut node = self.nodes[pos].borrow_mut();
+        if obstacle {
+            node.set_obstacle();
+        } else if node.node_type == NodeType::Obstacle {
+            node.node_type = NodeType::Traversable;
+        }
+    }
+
+    // drops everything derived from the obstacles, it is rebuilt on demand
+    fn edited(&mut self) {
+        *self.clearance.get_mut() = None;
+        *self.landmarks.get_mut() = None;
+        *self.components.get_mut() = None;
+    }
+
+    pub fn is_obstacle_at(&self, x: usize, y: usize) -> bool {
+        self.is_obstacle(&self.checked_pos(x, y))
+    }
+
+    // removing an obstacle can join two components, so unlike `set_obstacle`
+    // the labels are rebuilt from scratch. positions off the grid are ignored,
+    // as they are by `set_obstacle` and `toggle_obstacle`
+    pub fn clear_obstacle(&mut self, x: usize, y: usize) {
+        let pos = Position::new(x as i32, y as i32);
+        if self.is_obstacle(&pos) {
+            self.write_obstacle(&pos, false);
+            self.edited();
+        }
+    }
+
+    pub fn toggle_obstacle(&mut self, x: usize, y: usize) {
+        if self.is_obstacle(&Position::new(x as i32, y as i32)) {
+            self.clear_obstacle(x, y);
+        } else {
+            self.set_obstacle(x, y);
+        }
+    }
+
+    pub fn clear_start(&mut self) {
+        self.start = None;
+    }
+
+    pub fn clear_goal(&mut self) {
+        self.goal = None;
+    }
+
+    // sets or clears every node in the rectangle, clipped to the grid
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, obstacle: bool) {
+        for y in y..(y + height).min(self.height) {
+            for x in x..(x + width).min(self.width) {
+                self.write_obstacle(&Position::new(x as i32, y as i32), obstacle);
+            }
+        }
+        self.edited();
+    }
+
+    // sets or clears the nodes on the bresenham line between two nodes, both ends included
+    pub fn draw_line(&mut self, from: (usize, usize), to: (usize, usize), obstacle: bool) {
+        let from = self.checked_pos(from.0, from.1);
+        let to = self.checked_pos(to.0, to.1);
+        let (dx, dy) = ((to.x - from.x).abs(), -(to.y - from.y).abs());
+        let (step_x, step_y) = ((to.x - from.x).signum(), (to.y - from.y).signum());
+        let mut error = dx + dy;
+        let mut pos = from;
+        loop {
+            self.write_obstacle(&pos, obstacle);
+            if pos == to {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                pos.x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                pos.y += step_y;
+            }
+        }
+        self.edited();
+    }
+
+    // sets or clears the whole region of nodes that match the one at (x, y),
+    // spreading through edges only like a paint bucket
+    pub fn flood_fill(&mut self, x: usize, y: usize, obstacle: bool) {
+        let from = self.checked_pos(x, y);
+        let target = self.is_obstacle(&from);
+        if target == obstacle {
+            return;
+        }
+        self.write_obstacle(&from, obstacle);
+        let mut stack = vec![from];
+        while let Some(pos) = stack.pop() {
+            for next in self.get_neighbours(&pos) {
+                if self.is_obstacle(&next) == target {
+                    self.write_obstacle(&next, obstacle);
+                    stack.push(next);
+                }
+            }
+        }
+        self.edited();
+    }
+
+    // back to an empty grid: no obstacles, flat terrain, no start, goal or path
+    pub fn clear_all(&mut self) {
+        self.reset_search();
+        for node in self.nodes.values() {
+            let mut node = node.borrow_mut();
+            node.node_type = NodeType::Traversable;
+            node.terrain_cost = 1;
+        }
+        self.start = None;
+        self.goal = None;
+        self.reservations.clear();
+        self.edited();
+    }
+}
@@ -0,0 +1,130 @@
+use crate::cost::Cost;
+use crate::position::Position;
+use crate::Grid;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// cost-to-goal for every node plus the best step to take from it,
+// shared by any number of units heading to the same goals
+pub struct FlowField<C = usize> {
+    height: usize,
+    width: usize,
+    costs: Vec<Option<C>>,
+    // index of the node to step onto next, None at goals and unreachable nodes
+    next: Vec<Option<usize>>,
+}
+
+impl<C: Cost> FlowField<C> {
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    fn pos(&self, index: usize) -> Position {
+        Position::new((index % self.width) as i32, (index / self.width) as i32)
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        assert!(x < self.width && y < self.height, "({x}, {y}) is invalid");
+        self.width * y + x
+    }
+
+    // cost of the cheapest path to the nearest goal, None when unreachable
+    pub fn cost_at(&self, x: usize, y: usize) -> Option<C> {
+        self.costs[self.index(x, y)]
+    }
+
+    pub fn max_cost(&self) -> Option<C> {
+        self.costs.iter().flatten().max().copied()
+    }
+
+    // offset of the best neighbour to move onto, None at a goal or when unreachable
+    pub fn direction_at(&self, x: usize, y: usize) -> Option<Position> {
+        let next = self.pos(self.next[self.index(x, y)]?);
+        Some(Position::new(next.x - x as i32, next.y - y as i32))
+    }
+
+    // follows the field downhill, the path includes both ends
+    pub fn path_from(&self, x: usize, y: usize) -> Option<Vec<Position>> {
+        let mut index = self.index(x, y);
+        self.costs[index]?;
+        let mut path = vec![self.pos(index)];
+        while let Some(next) = self.next[index] {
+            index = next;
+            path.push(self.pos(index));
+        }
+        Some(path)
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // dijkstra outwards from every goal at once. steps are costed exactly as in
+    // `solve`, so the cost at a node equals the cost of solving from it
+    pub fn flow_field(&self, goals: &[(usize, usize)]) -> FlowField<C> {
+        let mut costs = vec![None; self.width * self.height];
+        let mut open_set = BinaryHeap::new();
+        for &(x, y) in goals {
+            let pos = Position::new(x as i32, y as i32);
+            assert!(self.is_valid_pos(&pos), "{:?} is invalid", pos);
+            if self.is_blocked(&pos) {
+                continue;
+            }
+            let index = self.get_index_from_pos(&pos);
+            costs[index] = Some(C::zero());
+            open_set.push(Reverse((C::zero(), index)));
+        }
+
+        while let Some(Reverse((cost, index))) = open_set.pop() {
+            if costs[index].is_some_and(|best| cost > best) {
+                continue;
+            }
+            let current_pos = self.get_pos_from_index(index);
+            let current_terrain = self.nodes[&current_pos].borrow().terrain_cost;
+            // moves are symmetric, but the cost of a move depends on the node
+            // it enters, so stepping from `pos` onto `current_pos` pays its terrain
+            for (pos, step) in self.get_steps(&current_pos) {
+                let step = step.times(current_terrain);
+                if step == C::infinity() {
+                    continue;
+                }
+                let neighbour = self.get_index_from_pos(&pos);
+                let new_cost = cost.saturating_add(step);
+                if new_cost == C::infinity() {
+                    continue;
+                }
+                if costs[neighbour].is_none_or(|best| new_cost < best) {
+                    costs[neighbour] = Some(new_cost);
+                    open_set.push(Reverse((new_cost, neighbour)));
+                }
+            }
+        }
+
+        let next = (0..costs.len())
+            .map(|index| {
+                let cost = costs[index]?;
+                if cost == C::zero() {
+                    return None;
+                }
+                let pos = self.get_pos_from_index(index);
+                self.get_move_costs(&pos)
+                    .into_iter()
+                    .filter_map(|(pos, step)| {
+                        let neighbour = self.get_index_from_pos(&pos);
+                        Some((costs[neighbour]?.saturating_add(step), neighbour))
+                    })
+                    .min()
+                    .map(|(_, neighbour)| neighbour)
+            })
+            .collect();
+
+        FlowField {
+            height: self.height,
+            width: self.width,
+            costs,
+            next,
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::cost::Cost;
+use crate::position::Position;
+use crate::{Grid, Heuristic};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Heading {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+// clockwise order, so neighbouring entries are 45 degrees apart
+const HEADINGS: [Heading; 8] = [
+    Heading::North,
+    Heading::NorthEast,
+    Heading::East,
+    Heading::SouthEast,
+    Heading::South,
+    Heading::SouthWest,
+    Heading::West,
+    Heading::NorthWest,
+];
+
+// slot used for the start when it has no heading yet
+const NO_HEADING: usize = HEADINGS.len();
+
+impl Heading {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn offset(self) -> Position {
+        match self {
+            Heading::North => Position::new(0, -1),
+            Heading::NorthEast => Position::new(1, -1),
+            Heading::East => Position::new(1, 0),
+            Heading::SouthEast => Position::new(1, 1),
+            Heading::South => Position::new(0, 1),
+            Heading::SouthWest => Position::new(-1, 1),
+            Heading::West => Position::new(-1, 0),
+            Heading::NorthWest => Position::new(-1, -1),
+        }
+    }
+
+    pub fn from_offset(offset: &Position) -> Option<Heading> {
+        HEADINGS
+            .into_iter()
+            .find(|heading| heading.offset() == *offset)
+    }
+
+    // number of 45 degree steps needed to face `other`, from 0 to 4
+    pub fn turns_to(self, other: Heading) -> usize {
+        let diff = (other.index() + HEADINGS.len() - self.index()) % HEADINGS.len();
+        diff.min(HEADINGS.len() - diff)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TurnSettings<C = usize> {
+    // extra cost for every 45 degrees turned, on the same scale as a step
+    // (`Cost::straight`)
+    pub turn_penalty: C,
+    pub forbid_u_turns: bool,
+    // direction the agent faces at the start, None lets the first move go anywhere
+    pub start_heading: Option<Heading>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeadingPath<C = usize> {
+    // every position from the start to the goal with the heading it was entered in,
+    // the start keeps its starting heading
+    pub steps: Vec<(Position, Option<Heading>)>,
+    pub cost: C,
+}
+
+impl<C: Cost> Grid<C> {
+    // A* over (position, heading) states, a turn costs `turn_penalty` for every
+    // 45 degrees on top of the normal step cost
+    pub fn solve_with_heading(
+        &self,
+        heuristic: &Heuristic,
+        settings: &TurnSettings<C>,
+    ) -> Option<HeadingPath<C>> {
+        let Some(start_pos) = self.start.clone() else {
+            panic!("no start position");
+        };
+        let Some(goal_pos) = self.goal.clone() else {
+            panic!("no goal position")
+        };
+        if !self.is_connected(&start_pos, &goal_pos) {
+            return None;
+        }
+
+        let heuristic = self.heuristic_function(heuristic);
+        let slots = HEADINGS.len() + 1;
+        let state = |pos: &Position, heading: Option<Heading>| {
+            self.get_index_from_pos(pos) * slots + heading.map_or(NO_HEADING, Heading::index)
+        };
+        let heading_of = |state: usize| HEADINGS.get(state % slots).copied();
+
+        let mut g_costs = vec![C::infinity(); self.width * self.height * slots];
+        let mut parents: Vec<Option<usize>> = vec![None; g_costs.len()];
+        let mut open_set = BinaryHeap::new();
+        let start = state(&start_pos, settings.start_heading);
+        g_costs[start] = C::zero();
+        open_set.push(Reverse((heuristic(&start_pos, &goal_pos), start)));
+
+        while let Some(Reverse((f_cost, current))) = open_set.pop() {
+            let pos = self.get_pos_from_index(current / slots);
+            let g_cost = g_costs[current];
+            if f_cost > g_cost.saturating_add(heuristic(&pos, &goal_pos)) {
+                continue; // stale entry
+            }
+            if pos == goal_pos {
+                let mut steps = vec![(pos, heading_of(current))];
+                let mut state = current;
+                while let Some(parent) = parents[state] {
+                    state = parent;
+                    steps.push((self.get_pos_from_index(state / slots), heading_of(state)));
+                }
+                steps.reverse();
+                return Some(HeadingPath {
+                    steps,
+                    cost: g_cost,
+                });
+            }
+            let heading = heading_of(current);
+            for (next_pos, step) in self.get_move_costs(&pos) {
+                let offset = Position::new(next_pos.x - pos.x, next_pos.y - pos.y);
+                let next_heading = Heading::from_offset(&offset).expect("neighbours are adjacent");
+                let turns = heading.map_or(0, |heading| heading.turns_to(next_heading));
+                if settings.forbid_u_turns && turns == HEADINGS.len() / 2 {
+                    continue;
+                }
+                let next = state(&next_pos, Some(next_heading));
+                let next_g_cost = g_cost
+                    .saturating_add(step)
+                    .saturating_add(settings.turn_penalty.times(turns));
+                if next_g_cost != C::infinity() && next_g_cost < g_costs[next] {
+                    g_costs[next] = next_g_cost;
+                    parents[next] = Some(current);
+                    let f_cost = next_g_cost.saturating_add(heuristic(&next_pos, &goal_pos));
+                    open_set.push(Reverse((f_cost, next)));
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,101 @@
+use crate::cost::Cost;
+use crate::position::Position;
+use crate::{Grid, Heuristic};
+
+// a node whose estimate is above the true cost to the goal
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overestimate<C = usize> {
+    pub pos: Position,
+    pub estimate: C,
+    pub distance: C,
+}
+
+// an edge along which the estimate drops by more than the step costs,
+// h(from) > step + h(to)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inconsistency<C = usize> {
+    pub from: Position,
+    pub to: Position,
+    pub step: C,
+    pub drop: C,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeuristicReport<C = usize> {
+    pub goal: Position,
+    pub inadmissible: Vec<Overestimate<C>>,
+    pub inconsistent: Vec<Inconsistency<C>>,
+}
+
+impl<C: Cost> HeuristicReport<C> {
+    pub fn is_admissible(&self) -> bool {
+        self.inadmissible.is_empty()
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistent.is_empty()
+    }
+
+    // by how much the estimate at this node is too high
+    pub fn overestimate_at(&self, x: usize, y: usize) -> Option<C> {
+        let pos = Position::new(x as i32, y as i32);
+        self.inadmissible
+            .iter()
+            .find(|overestimate| overestimate.pos == pos)
+            .map(|overestimate| overestimate.estimate - overestimate.distance)
+    }
+
+    // whether an edge leaving this node breaks consistency
+    pub fn is_inconsistent_at(&self, x: usize, y: usize) -> bool {
+        let pos = Position::new(x as i32, y as i32);
+        self.inconsistent
+            .iter()
+            .any(|inconsistency| inconsistency.from == pos)
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // compares `heuristic` against the true costs to `goal` for a 1x1 agent
+    // under the current movement rules (diagonals and terrain). nodes that
+    // can't reach the goal are skipped
+    pub fn check_heuristic(
+        &self,
+        heuristic: &Heuristic,
+        goal: (usize, usize),
+    ) -> HeuristicReport<C> {
+        let field = self.flow_field(&[goal]);
+        let goal = Position::new(goal.0 as i32, goal.1 as i32);
+        let heuristic = self.heuristic_function(heuristic);
+        let mut report = HeuristicReport {
+            goal: goal.clone(),
+            inadmissible: Vec::new(),
+            inconsistent: Vec::new(),
+        };
+        for index in 0..self.width * self.height {
+            let pos = self.get_pos_from_index(index);
+            let Some(distance) = field.cost_at(pos.x as usize, pos.y as usize) else {
+                continue;
+            };
+            let estimate = heuristic(&pos, &goal);
+            if estimate > distance {
+                report.inadmissible.push(Overestimate {
+                    pos: pos.clone(),
+                    estimate,
+                    distance,
+                });
+            }
+            for (next, step) in self.get_move_costs(&pos) {
+                let next_estimate = heuristic(&next, &goal);
+                if estimate > step + next_estimate {
+                    report.inconsistent.push(Inconsistency {
+                        from: pos.clone(),
+                        to: next,
+                        step,
+                        drop: estimate - next_estimate,
+                    });
+                }
+            }
+        }
+        report
+    }
+}
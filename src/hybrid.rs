@@ -0,0 +1,368 @@
+use crate::cost::Cost;
+use crate::position::Position;
+use crate::Grid;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::f32::consts::TAU;
+
+// continuous pose in node units, node (x, y) covers [x, x + 1) x [y, y + 1).
+// theta is in radians, measured from the x axis towards the y axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pose {
+    pub x: f32,
+    pub y: f32,
+    pub theta: f32,
+}
+
+impl Pose {
+    pub fn new(x: f32, y: f32, theta: f32) -> Self {
+        Pose { x, y, theta }
+    }
+
+    fn cell(&self) -> Position {
+        Position::new(self.x.floor() as i32, self.y.floor() as i32)
+    }
+
+    // drives `length` along an arc of the given curvature, 0 is straight
+    fn drive(&self, curvature: f32, length: f32) -> Pose {
+        if curvature.abs() < f32::EPSILON {
+            return Pose::new(
+                self.x + length * self.theta.cos(),
+                self.y + length * self.theta.sin(),
+                self.theta,
+            );
+        }
+        let radius = 1.0 / curvature;
+        let theta = self.theta + length * curvature;
+        Pose::new(
+            self.x + radius * (theta.sin() - self.theta.sin()),
+            self.y - radius * (theta.cos() - self.theta.cos()),
+            mod_two_pi(theta),
+        )
+    }
+}
+
+fn mod_two_pi(angle: f32) -> f32 {
+    angle.rem_euclid(TAU)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Turn {
+    Left,
+    Straight,
+    Right,
+}
+
+impl Turn {
+    fn curvature(self, radius: f32) -> f32 {
+        match self {
+            Turn::Left => 1.0 / radius,
+            Turn::Straight => 0.0,
+            Turn::Right => -1.0 / radius,
+        }
+    }
+}
+
+// shortest forward-only path between two poses for a car with a minimum
+// turning radius, made of three arc or straight segments
+#[derive(Debug, Clone, PartialEq)]
+pub struct DubinsPath {
+    start: Pose,
+    radius: f32,
+    segments: [(Turn, f32); 3],
+}
+
+impl DubinsPath {
+    pub fn shortest(start: Pose, goal: Pose, radius: f32) -> Self {
+        let dx = goal.x - start.x;
+        let dy = goal.y - start.y;
+        let d = (dx * dx + dy * dy).sqrt() / radius;
+        let phi = mod_two_pi(dy.atan2(dx));
+        let a = mod_two_pi(start.theta - phi);
+        let b = mod_two_pi(goal.theta - phi);
+        let (sa, sb, ca, cb) = (a.sin(), b.sin(), a.cos(), b.cos());
+        let c_ab = (a - b).cos();
+
+        use Turn::{Left as L, Right as R, Straight as S};
+        let mut words: Vec<([Turn; 3], [f32; 3])> = Vec::with_capacity(6);
+        let p_sq = 2.0 + d * d - 2.0 * c_ab + 2.0 * d * (sa - sb);
+        if p_sq >= 0.0 {
+            let tmp = (cb - ca).atan2(d + sa - sb);
+            words.push((
+                [L, S, L],
+                [mod_two_pi(tmp - a), p_sq.sqrt(), mod_two_pi(b - tmp)],
+            ));
+        }
+        let p_sq = 2.0 + d * d - 2.0 * c_ab + 2.0 * d * (sb - sa);
+        if p_sq >= 0.0 {
+            let tmp = (ca - cb).atan2(d - sa + sb);
+            words.push((
+                [R, S, R],
+                [mod_two_pi(a - tmp), p_sq.sqrt(), mod_two_pi(tmp - b)],
+            ));
+        }
+        let p_sq = -2.0 + d * d + 2.0 * c_ab + 2.0 * d * (sa + sb);
+        if p_sq >= 0.0 {
+            let p = p_sq.sqrt();
+            let tmp = (-ca - cb).atan2(d + sa + sb) - (-2.0f32).atan2(p);
+            words.push(([L, S, R], [mod_two_pi(tmp - a), p, mod_two_pi(tmp - b)]));
+        }
+        let p_sq = -2.0 + d * d + 2.0 * c_ab - 2.0 * d * (sa + sb);
+        if p_sq >= 0.0 {
+            let p = p_sq.sqrt();
+            let tmp = (ca + cb).atan2(d - sa - sb) - 2.0f32.atan2(p);
+            words.push(([R, S, L], [mod_two_pi(a - tmp), p, mod_two_pi(b - tmp)]));
+        }
+        let tmp = (6.0 - d * d + 2.0 * c_ab + 2.0 * d * (sa - sb)) / 8.0;
+        if tmp.abs() <= 1.0 {
+            let phi = (ca - cb).atan2(d - sa + sb);
+            let p = mod_two_pi(TAU - tmp.acos());
+            let t = mod_two_pi(a - phi + p / 2.0);
+            words.push(([R, L, R], [t, p, mod_two_pi(a - b - t + p)]));
+        }
+        let tmp = (6.0 - d * d + 2.0 * c_ab + 2.0 * d * (sb - sa)) / 8.0;
+        if tmp.abs() <= 1.0 {
+            let phi = (ca - cb).atan2(d + sa - sb);
+            let p = mod_two_pi(TAU - tmp.acos());
+            let t = mod_two_pi(-a - phi + p / 2.0);
+            words.push(([L, R, L], [t, p, mod_two_pi(b - a - t + p)]));
+        }
+
+        let (turns, lengths) = words
+            .into_iter()
+            .min_by(|x, y| x.1.iter().sum::<f32>().total_cmp(&y.1.iter().sum()))
+            .expect("LSL or RSR always exists");
+        DubinsPath {
+            start,
+            radius,
+            segments: [
+                (turns[0], lengths[0] * radius),
+                (turns[1], lengths[1] * radius),
+                (turns[2], lengths[2] * radius),
+            ],
+        }
+    }
+
+    pub fn length(&self) -> f32 {
+        self.segments.iter().map(|(_, length)| length).sum()
+    }
+
+    // pose after driving `distance` along the path
+    pub fn pose_at(&self, distance: f32) -> Pose {
+        let mut pose = self.start;
+        let mut remaining = distance.clamp(0.0, self.length());
+        for (turn, length) in self.segments {
+            let travel = remaining.min(length);
+            pose = pose.drive(turn.curvature(self.radius), travel);
+            remaining -= travel;
+        }
+        pose
+    }
+
+    // poses every `spacing` along the path, ending exactly on the goal
+    pub fn sample(&self, spacing: f32) -> Vec<Pose> {
+        let steps = (self.length() / spacing).ceil().max(1.0) as usize;
+        (1..=steps)
+            .map(|step| self.pose_at(self.length() * step as f32 / steps as f32))
+            .collect()
+    }
+}
+
+pub struct HybridSettings {
+    pub turning_radius: f32,
+    // arc length of each motion primitive
+    pub step: f32,
+    // states in the same node whose headings share a bin are pruned
+    pub heading_bins: usize,
+    // distance between collision checks along a motion
+    pub sample_spacing: f32,
+    // a dubins shot at the goal is tried every this many expansions
+    pub analytic_interval: usize,
+    pub max_expanded: usize,
+}
+
+impl Default for HybridSettings {
+    fn default() -> Self {
+        HybridSettings {
+            turning_radius: 2.0,
+            step: 1.0,
+            heading_bins: 36,
+            sample_spacing: 0.25,
+            analytic_interval: 5,
+            max_expanded: 20_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HybridPath {
+    // from the start to the goal pose, both included
+    pub poses: Vec<Pose>,
+    pub length: f32,
+}
+
+struct HybridNode {
+    pose: Pose,
+    g_cost: f32,
+    parent: Option<usize>,
+    // poses driven through on the way here from the parent
+    trail: Vec<Pose>,
+}
+
+struct Open {
+    f_cost: f32,
+    index: usize,
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f_cost
+            .total_cmp(&self.f_cost)
+            .then_with(|| self.index.cmp(&other.index))
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // whether a point sits inside a node the agent may occupy
+    pub fn is_pose_free(&self, pose: &Pose) -> bool {
+        let cell = pose.cell();
+        self.is_valid_pos(&cell) && !self.is_blocked(&cell)
+    }
+
+    fn hybrid_key(&self, pose: &Pose, bins: usize) -> (i32, i32, usize) {
+        let cell = pose.cell();
+        let bin = (mod_two_pi(pose.theta) / TAU * bins as f32) as usize % bins;
+        (cell.x, cell.y, bin)
+    }
+
+    // hybrid A*: nodes hold continuous poses reached by left, straight and right
+    // arcs at the minimum turning radius. the search finishes with a dubins curve
+    // so the goal pose is hit exactly
+    pub fn solve_hybrid(
+        &self,
+        start: Pose,
+        goal: Pose,
+        settings: &HybridSettings,
+    ) -> Option<HybridPath> {
+        assert!(
+            settings.turning_radius > 0.0,
+            "turning radius must be positive"
+        );
+        assert!(settings.step > 0.0, "step must be positive");
+        assert!(
+            settings.heading_bins > 0,
+            "there must be at least one heading bin"
+        );
+        assert!(
+            settings.sample_spacing > 0.0,
+            "sample spacing must be positive"
+        );
+        if !self.is_pose_free(&start) || !self.is_pose_free(&goal) {
+            return None;
+        }
+        if !self.is_connected(&start.cell(), &goal.cell()) {
+            return None;
+        }
+        let radius = settings.turning_radius;
+        let heuristic = |pose: &Pose| {
+            let straight = ((goal.x - pose.x).powi(2) + (goal.y - pose.y).powi(2)).sqrt();
+            straight.max(DubinsPath::shortest(*pose, goal, radius).length())
+        };
+        let trail = |from: &Pose, curvature: f32, length: f32| -> Vec<Pose> {
+            let steps = (length / settings.sample_spacing).ceil().max(1.0) as usize;
+            (1..=steps)
+                .map(|step| from.drive(curvature, length * step as f32 / steps as f32))
+                .collect()
+        };
+
+        let mut nodes = vec![HybridNode {
+            pose: start,
+            g_cost: 0.0,
+            parent: None,
+            trail: Vec::new(),
+        }];
+        let mut best: HashMap<(i32, i32, usize), f32> = HashMap::new();
+        best.insert(self.hybrid_key(&start, settings.heading_bins), 0.0);
+        let mut open_set = BinaryHeap::from([Open {
+            f_cost: heuristic(&start),
+            index: 0,
+        }]);
+
+        let mut expanded = 0;
+        while let Some(Open { index, .. }) = open_set.pop() {
+            let node = &nodes[index];
+            let key = self.hybrid_key(&node.pose, settings.heading_bins);
+            if best.get(&key).is_some_and(|&g_cost| node.g_cost > g_cost) {
+                continue; // stale entry
+            }
+            if expanded >= settings.max_expanded {
+                return None;
+            }
+            expanded += 1;
+
+            if (expanded - 1) % settings.analytic_interval.max(1) == 0 {
+                let shot = DubinsPath::shortest(node.pose, goal, radius);
+                let samples = shot.sample(settings.sample_spacing);
+                if samples.iter().all(|pose| self.is_pose_free(pose)) {
+                    let mut chain = vec![index];
+                    while let Some(parent) = nodes[*chain.last().expect("not empty")].parent {
+                        chain.push(parent);
+                    }
+                    let mut poses = vec![start];
+                    for &index in chain.iter().rev() {
+                        poses.extend(&nodes[index].trail);
+                    }
+                    poses.extend(samples);
+                    if let Some(last) = poses.last_mut() {
+                        *last = goal;
+                    }
+                    let length = node.g_cost + shot.length();
+                    return Some(HybridPath { poses, length });
+                }
+            }
+
+            let pose = node.pose;
+            let g_cost = node.g_cost;
+            for turn in [Turn::Left, Turn::Straight, Turn::Right] {
+                let motion = trail(&pose, turn.curvature(radius), settings.step);
+                if !motion.iter().all(|pose| self.is_pose_free(pose)) {
+                    continue;
+                }
+                let next = *motion.last().expect("at least one sample");
+                let next_g_cost = g_cost + settings.step;
+                let key = self.hybrid_key(&next, settings.heading_bins);
+                if best.get(&key).is_some_and(|&best| next_g_cost >= best) {
+                    continue;
+                }
+                best.insert(key, next_g_cost);
+                nodes.push(HybridNode {
+                    pose: next,
+                    g_cost: next_g_cost,
+                    parent: Some(index),
+                    trail: motion,
+                });
+                open_set.push(Open {
+                    f_cost: next_g_cost + heuristic(&next),
+                    index: nodes.len() - 1,
+                });
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,153 @@
+use crate::cost::Cost;
+use crate::position::Position;
+use crate::Grid;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::rc::Rc;
+
+// exact distances from and to a handful of landmarks, giving the ALT lower bound
+// d(v, t) >= max(d(L, t) - d(L, v), d(v, L) - d(t, L)) by the triangle inequality
+pub(crate) struct Landmarks<C> {
+    width: usize,
+    positions: Vec<Position>,
+    from_landmark: Vec<Vec<Option<C>>>,
+    to_landmark: Vec<Vec<Option<C>>>,
+    // the movement rules the tables were computed with
+    pub diagonal: bool,
+    pub agent_size: usize,
+}
+
+// a - b, or zero when b is larger
+fn difference<C: Cost>(a: C, b: C) -> C {
+    if a > b {
+        a - b
+    } else {
+        C::zero()
+    }
+}
+
+impl<C: Cost> Landmarks<C> {
+    fn index(&self, pos: &Position) -> usize {
+        self.width * (pos.y as usize) + (pos.x as usize)
+    }
+
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    pub fn estimate(&self, from: &Position, to: &Position) -> C {
+        let (from, to) = (self.index(from), self.index(to));
+        let forward = self
+            .from_landmark
+            .iter()
+            .filter_map(|distances| Some(difference(distances[to]?, distances[from]?)));
+        let backward = self
+            .to_landmark
+            .iter()
+            .filter_map(|distances| Some(difference(distances[from]?, distances[to]?)));
+        forward.chain(backward).max().unwrap_or(C::zero())
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // cost of the cheapest path from `source` to every node, or from every node
+    // to `source` when `reverse` is set. None when unreachable
+    fn distances(&self, source: &Position, reverse: bool, agent_size: usize) -> Vec<Option<C>> {
+        let mut costs = vec![None; self.width * self.height];
+        let source = self.get_index_from_pos(source);
+        costs[source] = Some(C::zero());
+        let mut open_set = BinaryHeap::from([Reverse((C::zero(), source))]);
+        while let Some(Reverse((cost, index))) = open_set.pop() {
+            if costs[index].is_some_and(|best| cost > best) {
+                continue;
+            }
+            let pos = self.get_pos_from_index(index);
+            let moves = if reverse {
+                // moves are symmetric apart from the terrain cost of the destination
+                let terrain_cost = self.terrain_cost_of(&pos);
+                self.get_steps_for(&pos, agent_size)
+                    .into_iter()
+                    .map(|(pos, step)| (pos, step.times(terrain_cost)))
+                    .filter(|(_, step)| *step != C::infinity())
+                    .collect()
+            } else {
+                self.get_move_costs_for(&pos, agent_size)
+            };
+            for (pos, step) in moves {
+                let next = self.get_index_from_pos(&pos);
+                let next_cost = cost.saturating_add(step);
+                if next_cost != C::infinity() && costs[next].is_none_or(|best| next_cost < best) {
+                    costs[next] = Some(next_cost);
+                    open_set.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+        costs
+    }
+
+    // farthest point selection: each landmark is the node furthest from the
+    // landmarks already chosen, which spreads them around the edges of the map
+    pub(crate) fn build_landmarks(&self, count: usize, agent_size: usize) -> Landmarks<C> {
+        let mut landmarks = Landmarks {
+            width: self.width,
+            positions: Vec::new(),
+            from_landmark: Vec::new(),
+            to_landmark: Vec::new(),
+            diagonal: self.allow_diagonal,
+            agent_size,
+        };
+        let free: Vec<Position> = (0..self.width * self.height)
+            .map(|index| self.get_pos_from_index(index))
+            .filter(|pos| !self.is_blocked_for(pos, agent_size))
+            .collect();
+        let Some(first) = free.first() else {
+            return landmarks;
+        };
+        let mut nearest = vec![C::infinity(); self.width * self.height];
+        let mut next = first.clone();
+        while landmarks.positions.len() < count.min(free.len()) {
+            let from_landmark = self.distances(&next, false, agent_size);
+            for (index, distance) in from_landmark.iter().enumerate() {
+                nearest[index] = nearest[index].min(distance.unwrap_or(C::infinity()));
+            }
+            nearest[self.get_index_from_pos(&next)] = C::zero();
+            landmarks
+                .to_landmark
+                .push(self.distances(&next, true, agent_size));
+            landmarks.from_landmark.push(from_landmark);
+            landmarks.positions.push(next);
+            next = free
+                .iter()
+                .max_by_key(|pos| nearest[self.get_index_from_pos(pos)])
+                .expect("there are free nodes")
+                .clone();
+        }
+        landmarks
+    }
+
+    // picks and measures `count` landmarks for the landmark heuristic. they are
+    // rebuilt with the same count after obstacle edits, movement rule changes or
+    // a search for another agent size
+    pub fn compute_landmarks(&mut self, count: usize) {
+        self.landmark_count = count;
+        *self.landmarks.get_mut() = Some(Rc::new(self.build_landmarks(count, 1)));
+    }
+
+    pub fn landmark_positions(&self) -> Vec<Position> {
+        self.current_landmarks(1).positions().to_vec()
+    }
+
+    pub(crate) fn current_landmarks(&self, agent_size: usize) -> Rc<Landmarks<C>> {
+        let stale = self.landmarks.borrow().as_ref().is_none_or(|landmarks| {
+            landmarks.diagonal != self.allow_diagonal || landmarks.agent_size != agent_size
+        });
+        if stale {
+            let landmarks = self.build_landmarks(self.landmark_count, agent_size);
+            *self.landmarks.borrow_mut() = Some(Rc::new(landmarks));
+        }
+        self.landmarks
+            .borrow()
+            .clone()
+            .expect("landmarks were just built")
+    }
+}
@@ -1,33 +1,128 @@
 #![feature(stmt_expr_attributes)]
+pub mod alternatives;
+pub mod anytime;
+pub mod ascii;
+#[cfg(feature = "image")]
+pub mod bitmap;
+mod clearance;
+mod connectivity;
+pub mod contraction;
+pub mod cost;
+mod editing;
+pub mod flow_field;
 pub mod frame_history;
+pub mod heading;
+pub mod heuristic_check;
+pub mod hybrid;
+mod landmarks;
+pub mod limits;
+pub mod maze;
+pub mod navmesh;
 pub mod node;
-mod position;
+pub mod position;
+mod rng;
+pub mod road;
+mod search;
+pub mod space_time;
+pub mod terrain;
 #[cfg(test)]
 mod test;
+pub mod transform;
+pub mod visibility;
+pub mod waypoints;
+pub mod world;
 
+use crate::connectivity::Components;
+use crate::cost::Cost;
+use crate::landmarks::Landmarks;
+use crate::limits::{SearchLimits, StopReason};
 use crate::node::{Node, NodeType};
 use crate::position::Position;
+use crate::search::{Search, SearchSpace};
 use std::cell::{Ref, RefCell};
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Heuristic {
     Manhattan,
     Diagonal,
     Euclidean,
+    // precomputed distances to landmarks (ALT), see `Grid::compute_landmarks`
+    Landmark,
 }
 
-pub struct Grid {
+impl Heuristic {
+    // the estimate from coordinates alone. landmarks need tables built for a
+    // bounded grid, see `Grid::heuristic_function`
+    pub(crate) fn geometric<C: Cost>(&self) -> HeuristicFn<C> {
+        let distance = |from: &Position, to: &Position| {
+            ((from.x - to.x).abs() as f64, (from.y - to.y).abs() as f64)
+        };
+        match self {
+            Heuristic::Manhattan => Box::new(move |from, to| {
+                let (dx, dy) = distance(from, to);
+                C::from_distance(dx + dy)
+            }),
+            Heuristic::Euclidean => Box::new(move |from, to| {
+                let (dx, dy) = distance(from, to);
+                C::from_distance(dx.hypot(dy))
+            }),
+            Heuristic::Diagonal => Box::new(move |from, to| {
+                let (dx, dy) = distance(from, to);
+                C::from_distance(dx.max(dy))
+            }),
+            Heuristic::Landmark => panic!("landmarks have no geometric estimate"),
+        }
+    }
+}
+
+// a heuristic that owns whatever it needs, so it doesn't borrow the grid
+pub(crate) type HeuristicFn<C> = Box<dyn Fn(&Position, &Position) -> C>;
+
+// default number of landmarks built on first use of `Heuristic::Landmark`
+const LANDMARK_COUNT: usize = 4;
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SolveResult {
+    Found,
+    // the start and goal are in different connected components
+    Unreachable,
+    // a search limit was hit, the path leads to the node found closest to the goal
+    Partial(StopReason),
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "C: serde::Serialize",
+        deserialize = "C: serde::Deserialize<'de>"
+    ))
+)]
+// costs are measured in `C`, see the `cost` module for the choices
+pub struct Grid<C = usize> {
     height: usize,
     width: usize,
-    nodes: HashMap<Position, Rc<RefCell<Node>>>,
+    #[cfg_attr(feature = "serde", serde(with = "node::serde_nodes"))]
+    nodes: HashMap<Position, Rc<RefCell<Node<C>>>>,
     goal: Option<Position>,
     start: Option<Position>,
     pub allow_diagonal: bool,
+    // nodes blocked at specific time steps, for space-time searches
+    reservations: HashSet<(Position, usize)>,
     path: Option<Vec<Position>>,
     pub duration: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    components: RefCell<Option<Components>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clearance: RefCell<Option<Vec<usize>>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    landmarks: RefCell<Option<Rc<Landmarks<C>>>>,
+    landmark_count: usize,
 }
 
 const OFFSETS: [Position; 4] = [
@@ -46,6 +141,13 @@ const DIAG_OFFSETS: [Position; 4] = [
 
 impl Grid {
     pub fn new(height: usize, width: usize) -> Self {
+        Grid::with_cost_type(height, width)
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // an empty grid measuring costs in `C`, e.g. `Grid::<OrderedFloat>::with_cost_type`
+    pub fn with_cost_type(height: usize, width: usize) -> Self {
         let mut nodes = HashMap::new();
         for y in 0..height {
             for x in 0..width {
@@ -64,16 +166,29 @@ impl Grid {
             goal: None,
             start: None,
             allow_diagonal: true,
+            reservations: HashSet::new(),
             path: None,
             duration: None,
+            components: RefCell::new(None),
+            clearance: RefCell::new(None),
+            landmarks: RefCell::new(None),
+            landmark_count: LANDMARK_COUNT,
         }
     }
 
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
     pub fn is_ready(&self) -> bool {
         self.goal.is_some() && self.start.is_some()
     }
 
-    pub fn get_node_at(&self, x: usize, y: usize) -> Ref<Node> {
+    pub fn get_node_at(&self, x: usize, y: usize) -> Ref<Node<C>> {
         let pos = Position::new(x as i32, y as i32);
         self.nodes
             .get(&pos)
@@ -99,24 +214,64 @@ impl Grid {
             .filter(|pos| self.is_valid_pos(pos))
             .collect()
     }
-    // returns the adjacent neighbours with cost(10)
-    fn get_neighbours_cost(&self, me: &Position) -> Vec<(Position, usize)> {
+    // returns the adjacent neighbours with a straight step cost (10)
+    fn get_neighbours_cost(&self, me: &Position) -> Vec<(Position, C)> {
         self.get_neighbours(me)
             .iter()
-            .map(|pos| (pos.clone(), 10))
+            .map(|pos| (pos.clone(), C::straight()))
             .collect()
     }
-    // returns the adjacent and diagonal neighbours with cost (14)
-    fn get_neighbours_diag_cost(&self, me: &Position) -> Vec<(Position, usize)> {
-        let mut diag_cost: Vec<(Position, usize)> = self
+    // returns the adjacent and diagonal neighbours with a diagonal step cost (14)
+    fn get_neighbours_diag_cost(&self, me: &Position) -> Vec<(Position, C)> {
+        let mut diag_cost: Vec<(Position, C)> = self
             .get_neighbours_diag(me)
             .iter()
-            .map(|pos| (pos.clone(), 14))
+            .map(|pos| (pos.clone(), C::diagonal()))
             .collect();
         let mut adjacent_cost = self.get_neighbours_cost(me);
         adjacent_cost.append(&mut diag_cost);
         adjacent_cost
     }
+    // returns the neighbours an agent `agent_size` nodes wide can step onto,
+    // with the unscaled step cost. agents occupy a square with their position
+    // as its top left node
+    fn get_steps_for(&self, me: &Position, agent_size: usize) -> Vec<(Position, C)> {
+        let mut neighbours = if self.allow_diagonal {
+            self.get_neighbours_diag_cost(me)
+        } else {
+            self.get_neighbours_cost(me)
+        };
+        neighbours.retain(|(pos, _)| !self.is_blocked_for(pos, agent_size));
+        neighbours
+    }
+
+    fn get_steps(&self, me: &Position) -> Vec<(Position, C)> {
+        self.get_steps_for(me, 1)
+    }
+
+    fn terrain_cost_of(&self, pos: &Position) -> usize {
+        self.nodes
+            .get(pos)
+            .map_or(1, |node| node.borrow().terrain_cost)
+    }
+
+    // returns the neighbours that can be stepped onto, with the step cost
+    // scaled by the terrain cost of the destination. steps too costly for `C`
+    // to hold are left out
+    fn get_move_costs_for(&self, me: &Position, agent_size: usize) -> Vec<(Position, C)> {
+        self.get_steps_for(me, agent_size)
+            .into_iter()
+            .map(|(pos, cost)| {
+                let terrain_cost = self.terrain_cost_of(&pos);
+                (pos, cost.times(terrain_cost))
+            })
+            .filter(|(_, cost)| *cost != C::infinity())
+            .collect()
+    }
+
+    fn get_move_costs(&self, me: &Position) -> Vec<(Position, C)> {
+        self.get_move_costs_for(me, 1)
+    }
 
     fn get_index_from_pos(&self, pos: &Position) -> usize {
         self.width * (pos.y as usize) + (pos.x as usize)
@@ -130,9 +285,31 @@ impl Grid {
 
     pub fn set_obstacle(&mut self, x: usize, y: usize) {
         let pos = Position::new(x as i32, y as i32);
+        if !self.is_valid_pos(&pos) {
+            return;
+        }
+        self.nodes[&pos].borrow_mut().set_obstacle();
+        *self.clearance.get_mut() = None;
+        *self.landmarks.get_mut() = None;
+        let components = self.components.get_mut();
+        if components
+            .as_ref()
+            .is_some_and(|components| components.agent_size > 1)
+        {
+            // the obstacle blocks a whole square of positions for large agents
+            *components = None;
+        } else if let Some(components) = self.components.borrow_mut().as_mut() {
+            components.obstacle_added(self, &pos);
+        }
+    }
+
+    pub fn set_terrain_cost(&mut self, x: usize, y: usize, cost: usize) {
+        assert!(cost > 0, "terrain cost must be at least 1");
+        let pos = Position::new(x as i32, y as i32);
+        *self.landmarks.get_mut() = None;
         self.nodes
             .entry(pos)
-            .and_modify(|node| node.borrow_mut().set_obstacle());
+            .and_modify(|node| node.borrow_mut().terrain_cost = cost);
     }
 
     pub fn set_start(&mut self, x: usize, y: usize) {
@@ -145,6 +322,14 @@ impl Grid {
         self.goal = Some(pos);
     }
 
+    pub fn start(&self) -> Option<&Position> {
+        self.start.as_ref()
+    }
+
+    pub fn goal(&self) -> Option<&Position> {
+        self.goal.as_ref()
+    }
+
     pub fn is_goal(&self, x: usize, y: usize) -> bool {
         let pos = Position::new(x as i32, y as i32);
         Some(pos) == self.goal
@@ -155,6 +340,36 @@ impl Grid {
         Some(pos) == self.start
     }
 
+    pub(crate) fn heuristic_function(&self, heuristic: &Heuristic) -> HeuristicFn<C> {
+        self.heuristic_function_for(heuristic, 1)
+    }
+
+    // landmark distances depend on which nodes the agent fits through
+    fn heuristic_function_for(&self, heuristic: &Heuristic, agent_size: usize) -> HeuristicFn<C> {
+        match heuristic {
+            Heuristic::Landmark => {
+                let landmarks = self.current_landmarks(agent_size);
+                Box::new(move |from, to| landmarks.estimate(from, to))
+            }
+            _ => heuristic.geometric(),
+        }
+    }
+
+    // clears the costs and markings left behind by a previous solve
+    fn reset_search(&mut self) {
+        self.path = None;
+        for node in self.nodes.values() {
+            let mut node = node.borrow_mut();
+            if node.node_type != NodeType::Obstacle {
+                node.node_type = NodeType::Traversable;
+            }
+            node.g_cost = C::infinity();
+            node.h_cost = C::infinity();
+            node.f_cost = C::infinity();
+            node.parent = None;
+        }
+    }
+
     fn trace_path(&mut self, position: Position) {
         let mut path_pos: Vec<Position> = Vec::new();
         path_pos.push(position.clone());
@@ -170,7 +385,36 @@ impl Grid {
         self.path = Some(path_pos);
     }
 
-    pub fn solve(&mut self, heuristic: &Heuristic) {
+    // the path runs from its end back to the start
+    pub fn path(&self) -> Option<&[Position]> {
+        self.path.as_deref()
+    }
+
+    pub fn solve(&mut self, heuristic: &Heuristic) -> SolveResult {
+        self.solve_with_limits(heuristic, &SearchLimits::default())
+    }
+
+    pub fn solve_with_limits(
+        &mut self,
+        heuristic: &Heuristic,
+        limits: &SearchLimits,
+    ) -> SolveResult {
+        self.solve_for_agent_with_limits(heuristic, 1, limits)
+    }
+
+    // solves for an agent that occupies a square `agent_size` nodes wide, with
+    // its position as the top left node. only nodes with enough clearance are
+    // expanded, the clearance map is shared by every agent size
+    pub fn solve_for_agent(&mut self, heuristic: &Heuristic, agent_size: usize) -> SolveResult {
+        self.solve_for_agent_with_limits(heuristic, agent_size, &SearchLimits::default())
+    }
+
+    pub fn solve_for_agent_with_limits(
+        &mut self,
+        heuristic: &Heuristic,
+        agent_size: usize,
+        limits: &SearchLimits,
+    ) -> SolveResult {
         let start_pos = self.start.clone();
         let Some(start_pos) = start_pos else {
             panic!("no start position");
@@ -180,76 +424,44 @@ impl Grid {
             panic!("no goal position")
         };
 
-        let heuristic: fn(&Position, &Position) -> usize = match heuristic {
-            Heuristic::Manhattan => position::manhattan_distance,
-            Heuristic::Euclidean => position::euclidean_distance,
-            Heuristic::Diagonal => position::diagonal_distance,
-        };
+        self.reset_search();
+        if !self.is_connected_for(&start_pos, &goal_pos, agent_size) {
+            self.duration = None;
+            return SolveResult::Unreachable;
+        }
 
-        let goal = self.get_index_from_pos(&goal_pos);
-
-        let mut open_set = BinaryHeap::new();
-        let start_node = self
-            .nodes
-            .get(&start_pos)
-            .expect("must be valid start position");
-        let start_h_cost = heuristic(&start_pos, &goal_pos);
-        start_node.borrow_mut().h_cost = start_h_cost;
-        start_node.borrow_mut().g_cost = 0;
-        start_node.borrow_mut().f_cost = start_h_cost;
-
-        // start_node.node_type = NodeType::Traversed;
-        open_set.push(start_node.clone());
-
-        #[cfg(not(target_arch = "wasm32"))]
-        let now = Instant::now();
-
-        while let Some(current_node) = open_set.pop() {
-            #[cfg(not(target_arch = "wasm32"))]
-            let duration = Some(now.elapsed());
-            #[cfg(target_arch = "wasm32")]
-            let duration = None;
-            self.duration = duration;
-
-            if current_node.borrow().index == goal {
-                self.trace_path(
-                    current_node
-                        .borrow()
-                        .parent
-                        .clone()
-                        .expect("need to have a parent"),
-                );
-                break;
-            }
-            let current_pos = self.get_pos_from_index(current_node.borrow().index);
-            let neighbours = if self.allow_diagonal {
-                self.get_neighbours_diag_cost(&current_pos)
-            } else {
-                self.get_neighbours_cost(&current_pos)
-            };
-            for (pos, cost) in neighbours {
-                let neighbour = self.nodes.get(&pos).expect("invalid position");
-                if neighbour.borrow().node_type == NodeType::Obstacle {
-                    continue;
-                }
-                let temp_g_cost = current_node.borrow().g_cost + cost;
-                if temp_g_cost > neighbour.borrow().g_cost {
-                    continue; // this way would have been a worse path
-                }
-                let g_cost = temp_g_cost;
-                let h_cost = heuristic(&pos, &goal_pos);
-                let f_cost = g_cost + h_cost;
-                neighbour.borrow_mut().g_cost = g_cost;
-                neighbour.borrow_mut().h_cost = h_cost;
-                neighbour.borrow_mut().f_cost = f_cost;
-                neighbour.borrow_mut().parent = Some(current_pos.clone());
-                if neighbour.borrow().node_type == NodeType::Traversed {
-                    continue;
-                }
-                neighbour.borrow_mut().node_type = NodeType::Traversed;
-                open_set.push(neighbour.clone());
+        let heuristic = self.heuristic_function_for(heuristic, agent_size);
+        let space = Agent {
+            grid: &*self,
+            size: agent_size,
+        };
+        let mut search = Search::new(&space, start_pos.clone(), goal_pos.clone(), heuristic);
+        let result = search.run(limits);
+        // copy the search onto the nodes, so it can be drawn
+        for (pos, &g_cost) in &search.g_costs {
+            let mut node = self.nodes[pos].borrow_mut();
+            let h_cost = search.h_costs[pos];
+            node.g_cost = g_cost;
+            node.h_cost = h_cost;
+            node.f_cost = g_cost.saturating_add(h_cost);
+            node.parent = search.parents.get(pos).cloned();
+            if *pos != start_pos {
+                node.node_type = NodeType::Traversed;
             }
         }
+        // the path runs back from where the search ended, leaving out the goal.
+        // a goal on the start is reached by the one node path
+        let end = match result {
+            SolveResult::Found if goal_pos == start_pos => Some(start_pos.clone()),
+            SolveResult::Found => search.parents.get(&goal_pos).cloned(),
+            SolveResult::Partial(_) => Some(search.best().clone()),
+            SolveResult::Unreachable => None,
+        };
+        let duration = search.elapsed();
+        self.duration = duration;
+        if let Some(end) = end {
+            self.trace_path(end);
+        }
         if let Some(path) = &self.path {
             // set all the nodes to path
             path.iter().for_each(|pos| {
@@ -258,7 +470,20 @@ impl Grid {
                     .expect("node should exist")
                     .borrow_mut()
                     .node_type = NodeType::Path;
-            })
+            });
         }
+        result
+    }
+}
+
+// the grid as seen by an agent `size` nodes wide
+struct Agent<'a, C> {
+    grid: &'a Grid<C>,
+    size: usize,
+}
+
+impl<C: Cost> SearchSpace<C> for Agent<'_, C> {
+    fn successors(&self, pos: &Position) -> Vec<(Position, C)> {
+        self.grid.get_move_costs_for(pos, self.size)
     }
 }
@@ -0,0 +1,53 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// shared flag that asks a running search to stop, clone it into another thread
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// caps on a single search, `None` means unlimited.
+// the time budget is ignored on wasm where there is no clock
+#[derive(Clone, Default, Debug)]
+pub struct SearchLimits {
+    pub max_expanded: Option<usize>,
+    pub max_duration: Option<Duration>,
+    pub cancel: Option<CancelToken>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StopReason {
+    NodeBudget,
+    TimeBudget,
+    Cancelled,
+}
+
+impl SearchLimits {
+    pub(crate) fn check(&self, expanded: usize, elapsed: Option<Duration>) -> Option<StopReason> {
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            return Some(StopReason::Cancelled);
+        }
+        if self.max_expanded.is_some_and(|max| expanded >= max) {
+            return Some(StopReason::NodeBudget);
+        }
+        match (self.max_duration, elapsed) {
+            (Some(max), Some(elapsed)) if elapsed >= max => Some(StopReason::TimeBudget),
+            _ => None,
+        }
+    }
+}
@@ -1,8 +1,18 @@
 use eframe::egui::{Context, Sense};
 use eframe::{egui, Frame};
+use path_finding::alternatives::{Alternative, AlternativeSettings};
+use path_finding::flow_field::FlowField;
 use path_finding::frame_history::FrameHistory;
+use path_finding::heuristic_check::HeuristicReport;
+use path_finding::maze::MazeAlgorithm;
+use path_finding::navmesh::NavMesh;
 use path_finding::node::{Node, NodeType};
-use path_finding::{Grid, Heuristic};
+use path_finding::transform::Anchor;
+use path_finding::waypoints::WaypointRoute;
+use path_finding::{Grid, Heuristic, SolveResult};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
 
 #[cfg(target_arch = "wasm32")]
 fn main() {
@@ -27,6 +37,12 @@ fn main() {
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() {
+    // `route ...` answers road graph queries instead of opening the window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("route") {
+        std::process::exit(cli::route(&args[1..]));
+    }
+
     let icon_data = eframe::IconData::try_from_png_bytes(include_bytes!("assets/icon.png")).ok();
     let options = eframe::NativeOptions {
         icon_data,
@@ -45,6 +61,8 @@ enum CursorType {
     Goal,
     Obstacle,
     Start,
+    Erase,
+    Waypoint,
 }
 
 trait NodeColor {
@@ -64,6 +82,108 @@ impl NodeColor for Node {
 
 const WIDGET_SPACING: f32 = 10.0;
 
+// heatmap of the cost to the goal with an arrow along the best direction
+fn draw_flow_cell(
+    painter: &egui::Painter,
+    field: &FlowField,
+    x: usize,
+    y: usize,
+    rect: egui::Rect,
+) {
+    let (Some(cost), Some(max_cost)) = (field.cost_at(x, y), field.max_cost()) else {
+        return;
+    };
+    let heat = cost as f32 / max_cost.max(1) as f32;
+    let color = egui::Color32::from_rgba_unmultiplied(255, (255.0 * (1.0 - heat)) as u8, 0, 120);
+    painter.rect_filled(rect, egui::Rounding::default(), color);
+    if let Some(direction) = field.direction_at(x, y) {
+        let length = rect.width().min(rect.height()) * 0.35;
+        let vec = egui::vec2(direction.x as f32, direction.y as f32).normalized() * length;
+        let stroke = egui::Stroke::new(1.5, egui::Color32::DARK_GRAY);
+        painter.arrow(rect.center() - vec / 2.0, vec, stroke);
+    }
+}
+
+// outlines nodes where the heuristic overestimates (red) or drops by more
+// than a step along some edge (orange)
+fn draw_heuristic_cell(
+    painter: &egui::Painter,
+    report: &HeuristicReport,
+    x: usize,
+    y: usize,
+    rect: egui::Rect,
+) {
+    let rect = rect.shrink(3.0);
+    if report.overestimate_at(x, y).is_some() {
+        let stroke = egui::Stroke::new(3.0, egui::Color32::RED);
+        painter.rect_stroke(rect, egui::Rounding::default(), stroke);
+    } else if report.is_inconsistent_at(x, y) {
+        let stroke = egui::Stroke::new(3.0, egui::Color32::from_rgb(255, 140, 0));
+        painter.rect_stroke(rect, egui::Rounding::default(), stroke);
+    }
+}
+
+// outlines every navmesh region in its own colour
+fn draw_navmesh(painter: &egui::Painter, navmesh: &NavMesh, cell_size: egui::Vec2) {
+    for (id, region) in navmesh.regions() {
+        let min = egui::pos2(
+            region.x as f32 * cell_size.x + 10.0,
+            region.y as f32 * cell_size.y + 10.0,
+        );
+        let size = egui::vec2(
+            region.width as f32 * cell_size.x,
+            region.height as f32 * cell_size.y,
+        );
+        let rect = egui::Rect::from_min_size(min, size).shrink(2.0);
+        let hue = (id as f32 * 0.618).fract();
+        let color = egui::ecolor::Hsva::new(hue, 0.8, 0.8, 1.0);
+        painter.rect_stroke(
+            rect,
+            egui::Rounding::same(3.0),
+            egui::Stroke::new(2.0, color),
+        );
+    }
+}
+
+// a numbered marker showing the order waypoints were placed in
+fn draw_waypoint(painter: &egui::Painter, index: usize, rect: egui::Rect) {
+    let radius = rect.width().min(rect.height()) / 3.0;
+    painter.circle_filled(rect.center(), radius, egui::Color32::GOLD);
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        format!("{}", index + 1),
+        egui::FontId::proportional(radius),
+        egui::Color32::BLACK,
+    );
+}
+
+// draws each route choice as a line through the node centres, offset a little
+// so routes sharing nodes stay visible
+fn draw_routes(painter: &egui::Painter, routes: &[Alternative], cell_size: egui::Vec2) {
+    let colors = [
+        egui::Color32::from_rgb(230, 60, 60),
+        egui::Color32::from_rgb(60, 120, 230),
+        egui::Color32::from_rgb(240, 170, 30),
+        egui::Color32::from_rgb(150, 60, 200),
+    ];
+    for (i, route) in routes.iter().enumerate() {
+        let offset = (i as f32 - (routes.len() - 1) as f32 / 2.0) * 3.0;
+        let points = route
+            .path
+            .iter()
+            .map(|pos| {
+                egui::pos2(
+                    (pos.x as f32 + 0.5) * cell_size.x + 10.0 + offset,
+                    (pos.y as f32 + 0.5) * cell_size.y + 10.0 + offset,
+                )
+            })
+            .collect();
+        let stroke = egui::Stroke::new(3.0, colors[i % colors.len()]);
+        painter.add(egui::Shape::line(points, stroke));
+    }
+}
+
 struct MyApp {
     height: usize,
     width: usize,
@@ -76,6 +196,22 @@ struct MyApp {
     new_width: usize,
     show_cost: bool,
     heuristic: Heuristic,
+    // side length of the square agent that paths are found for
+    agent_size: usize,
+    maze_algorithm: MazeAlgorithm,
+    maze_seed: u64,
+    result: Option<SolveResult>,
+    flow_field: Option<FlowField>,
+    heuristic_report: Option<HeuristicReport>,
+    navmesh: Option<NavMesh>,
+    routes: Vec<Alternative>,
+    waypoints: Vec<(usize, usize)>,
+    best_order: bool,
+    waypoint_route: Option<WaypointRoute>,
+    #[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+    image_path: String,
+    #[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+    image_error: Option<String>,
 }
 
 impl MyApp {
@@ -93,8 +229,96 @@ impl MyApp {
             new_width: width,
             show_cost: true,
             heuristic: Heuristic::Manhattan,
+            agent_size: 1,
+            maze_algorithm: MazeAlgorithm::RecursiveBacktracker,
+            maze_seed: 0,
+            result: None,
+            flow_field: None,
+            heuristic_report: None,
+            navmesh: None,
+            routes: Vec::new(),
+            waypoints: Vec::new(),
+            best_order: false,
+            waypoint_route: None,
+            #[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+            image_path: String::new(),
+            #[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+            image_error: None,
         }
     }
+
+    fn set_grid(&mut self, grid: Grid, width: usize, height: usize) {
+        self.grid = grid;
+        self.width = width;
+        self.height = height;
+        self.new_width = width;
+        self.new_height = height;
+        self.result = None;
+        self.flow_field = None;
+        self.heuristic_report = None;
+        self.navmesh = None;
+        self.routes.clear();
+        self.waypoints.clear();
+        self.waypoint_route = None;
+    }
+
+    fn maze_controls(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            egui::ComboBox::from_label("Maze")
+                .selected_text(format!("{:?}", self.maze_algorithm))
+                .show_ui(ui, |ui| {
+                    for algorithm in [
+                        MazeAlgorithm::RecursiveBacktracker,
+                        MazeAlgorithm::Prim,
+                        MazeAlgorithm::Kruskal,
+                        MazeAlgorithm::RecursiveDivision,
+                    ] {
+                        ui.selectable_value(
+                            &mut self.maze_algorithm,
+                            algorithm,
+                            format!("{algorithm:?}"),
+                        );
+                    }
+                });
+            ui.add(egui::DragValue::new(&mut self.maze_seed).prefix("Seed: "));
+            if ui.button("Generate Maze").clicked() {
+                let (width, height) = (self.new_width, self.new_height);
+                let grid = Grid::maze(height, width, &self.maze_algorithm, self.maze_seed);
+                self.set_grid(grid, width, height);
+                self.maze_seed = self.maze_seed.wrapping_add(1);
+            }
+        });
+    }
+
+    #[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+    fn image_controls(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.label("Image map");
+            ui.text_edit_singleline(&mut self.image_path);
+            ui.horizontal(|ui| {
+                if ui.button("Open Image").clicked() {
+                    match Grid::load_image(&self.image_path) {
+                        Ok(grid) => {
+                            let (width, height) = (grid.width(), grid.height());
+                            self.set_grid(grid, width, height);
+                            self.image_error = None;
+                        }
+                        Err(err) => self.image_error = Some(err.to_string()),
+                    }
+                }
+                if ui.button("Export Image").clicked() {
+                    self.image_error = self
+                        .grid
+                        .save_image(&self.image_path)
+                        .err()
+                        .map(|err| err.to_string());
+                }
+            });
+            if let Some(err) = &self.image_error {
+                ui.colored_label(egui::Color32::LIGHT_RED, err);
+            }
+        });
+    }
 }
 
 impl eframe::App for MyApp {
@@ -119,6 +343,12 @@ impl eframe::App for MyApp {
                                 "Obstacle",
                             );
                             ui.selectable_value(&mut self.cursor_type, CursorType::Goal, "Goal");
+                            ui.selectable_value(&mut self.cursor_type, CursorType::Erase, "Erase");
+                            ui.selectable_value(
+                                &mut self.cursor_type,
+                                CursorType::Waypoint,
+                                "Waypoint",
+                            );
                         });
                     ui.add_space(WIDGET_SPACING);
                     ui.group(|ui| {
@@ -132,13 +362,40 @@ impl eframe::App for MyApp {
                                 .text("Grid width")
                                 .integer(),
                         );
-                        if ui.button("Rebuild Grid").clicked() {
-                            self.height = self.new_height;
-                            self.width = self.new_width;
-                            self.grid = Grid::new(self.new_height, self.new_width);
+                        if ui.button("Resize Grid").clicked() {
+                            let (width, height) = (self.new_width, self.new_height);
+                            let grid = self.grid.resize(height, width, Anchor::TopLeft);
+                            self.set_grid(grid, width, height);
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Rotate").clicked() {
+                                let grid = self.grid.rotate_clockwise();
+                                self.set_grid(grid, self.height, self.width);
+                            }
+                            if ui.button("Flip").clicked() {
+                                let grid = self.grid.flip_horizontal();
+                                self.set_grid(grid, self.width, self.height);
+                            }
+                        });
+                        if ui.button("Clear Grid").clicked() {
+                            self.grid.clear_all();
+                            self.result = None;
+                            self.flow_field = None;
+                            self.heuristic_report = None;
+                            self.navmesh = None;
+                            self.routes.clear();
+                            self.waypoints.clear();
+                            self.waypoint_route = None;
                         }
                     });
                     ui.add_space(WIDGET_SPACING);
+                    self.maze_controls(ui);
+                    #[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+                    {
+                        ui.add_space(WIDGET_SPACING);
+                        self.image_controls(ui);
+                    }
+                    ui.add_space(WIDGET_SPACING);
                     ui.label("Heuristic");
                     ui.vertical_centered(|ui| {
                         ui.horizontal(|ui| {
@@ -158,21 +415,96 @@ impl eframe::App for MyApp {
                                 Heuristic::Euclidean,
                                 "Euclidean",
                             );
+                            ui.selectable_value(
+                                &mut self.heuristic,
+                                Heuristic::Landmark,
+                                "Landmark",
+                            );
                         });
                     });
                     ui.end_row();
                     ui.add_space(WIDGET_SPACING);
                     ui.checkbox(&mut self.grid.allow_diagonal, "Move Diagonally");
                     ui.add_space(WIDGET_SPACING);
+                    let agent_size = ui.add(
+                        egui::Slider::new(&mut self.agent_size, 1..=5)
+                            .text("Agent size")
+                            .integer(),
+                    );
+                    // the mesh only fits the agent size it was built for
+                    if agent_size.changed() {
+                        if let Some(navmesh) = &mut self.navmesh {
+                            *navmesh = self.grid.navmesh(self.agent_size);
+                        }
+                    }
+                    ui.add_space(WIDGET_SPACING);
                     ui.checkbox(&mut self.show_cost, "Show Cost");
                     ui.add_space(WIDGET_SPACING);
                     ui.add_enabled_ui(self.grid.is_ready(), |ui| {
                         if ui.button("Find Path").clicked() {
-                            self.grid.solve(&self.heuristic);
+                            self.result =
+                                Some(self.grid.solve_for_agent(&self.heuristic, self.agent_size));
+                        }
+                        if ui.button("Route Choices").clicked() {
+                            self.routes = self.grid.alternative_routes(
+                                &AlternativeSettings::default(),
+                                &self.heuristic,
+                            );
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Visit Waypoints").clicked() {
+                                self.waypoint_route = if self.best_order {
+                                    self.grid
+                                        .solve_waypoints_any_order(&self.waypoints, &self.heuristic)
+                                } else {
+                                    self.grid.solve_waypoints(&self.waypoints, &self.heuristic)
+                                };
+                                self.result = Some(match self.waypoint_route {
+                                    Some(_) => SolveResult::Found,
+                                    None => SolveResult::Unreachable,
+                                });
+                            }
+                            ui.checkbox(&mut self.best_order, "Best Order");
+                        });
+                    });
+                    if ui.button("Nav Mesh").clicked() {
+                        self.navmesh = match self.navmesh {
+                            Some(_) => None,
+                            None => Some(self.grid.navmesh(self.agent_size)),
+                        };
+                    }
+                    ui.add_enabled_ui(self.grid.goal().is_some(), |ui| {
+                        if ui.button("Flow Field").clicked() {
+                            self.flow_field = self.grid.goal().map(|goal| {
+                                self.grid.flow_field(&[(goal.x as usize, goal.y as usize)])
+                            });
+                        }
+                        if ui.button("Check Heuristic").clicked() {
+                            self.heuristic_report = self.grid.goal().map(|goal| {
+                                self.grid.check_heuristic(
+                                    &self.heuristic,
+                                    (goal.x as usize, goal.y as usize),
+                                )
+                            });
                         }
                     });
                     ui.separator();
                     ui.label(format!("FPS: {:.1}", self.frame_history.fps()));
+                    if self.result == Some(SolveResult::Unreachable) {
+                        ui.label("Goal is unreachable");
+                    }
+                    if let Some(report) = &self.heuristic_report {
+                        ui.label(format!(
+                            "Overestimates: {}, inconsistent edges: {}",
+                            report.inadmissible.len(),
+                            report.inconsistent.len()
+                        ));
+                    }
+                    if let Some(route) = &self.waypoint_route {
+                        let legs: Vec<String> =
+                            route.legs.iter().map(|cost| cost.to_string()).collect();
+                        ui.label(format!("Legs: {} (total {})", legs.join(" + "), route.cost));
+                    }
                     if let Some(duration) = self.grid.duration {
                         ui.label(format!("Completed in: {:.1} μs", duration.as_micros()));
                     }
@@ -201,6 +533,15 @@ impl eframe::App for MyApp {
                         color = egui::Color32::LIGHT_BLUE;
                     }
                     painter.rect_filled(rect, self.rounding, color);
+                    if let Some(index) = self.waypoints.iter().position(|&pos| pos == (x, y)) {
+                        draw_waypoint(&painter, index, rect);
+                    }
+                    if let Some(field) = &self.flow_field {
+                        draw_flow_cell(&painter, field, x, y, rect);
+                    }
+                    if let Some(report) = &self.heuristic_report {
+                        draw_heuristic_cell(&painter, report, x, y, rect);
+                    }
                     painter.rect_stroke(rect, self.rounding, self.stroke);
                     ui.allocate_ui_at_rect(rect, |ui| {
                         ui.add_visible_ui(self.show_cost, |ui| {
@@ -223,15 +564,43 @@ impl eframe::App for MyApp {
                         });
                         let (_, res) = ui.allocate_exact_size(rect_size, Sense::click());
                         if res.clicked() {
+                            self.flow_field = None;
+                            self.heuristic_report = None;
+                            self.routes.clear();
+                            self.waypoint_route = None;
                             match self.cursor_type {
                                 CursorType::Goal => self.grid.set_goal(x, y),
                                 CursorType::Obstacle => self.grid.set_obstacle(x, y),
                                 CursorType::Start => self.grid.set_start(x, y),
+                                CursorType::Erase => {
+                                    self.grid.clear_obstacle(x, y);
+                                    if self.grid.is_start(x, y) {
+                                        self.grid.clear_start();
+                                    }
+                                    if self.grid.is_goal(x, y) {
+                                        self.grid.clear_goal();
+                                    }
+                                    self.waypoints.retain(|&pos| pos != (x, y));
+                                }
+                                CursorType::Waypoint => {
+                                    if self.waypoints.contains(&(x, y)) {
+                                        self.waypoints.retain(|&pos| pos != (x, y));
+                                    } else {
+                                        self.waypoints.push((x, y));
+                                    }
+                                }
+                            }
+                            if let Some(navmesh) = &mut self.navmesh {
+                                navmesh.update(&self.grid, x, y, 1, 1);
                             }
                         }
                     });
                 }
             }
+            if let Some(navmesh) = &self.navmesh {
+                draw_navmesh(&painter, navmesh, rect_size);
+            }
+            draw_routes(&painter, &self.routes, rect_size);
         });
     }
 }
@@ -0,0 +1,258 @@
+use crate::cost::Cost;
+use crate::rng::Rng;
+use crate::Grid;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MazeAlgorithm {
+    RecursiveBacktracker,
+    Prim,
+    Kruskal,
+    RecursiveDivision,
+}
+
+// maze cells sit on odd coordinates, the even rows and columns between them are walls
+struct Maze {
+    height: usize,
+    width: usize,
+    walls: Vec<bool>,
+}
+
+impl Maze {
+    fn new(height: usize, width: usize, wall: bool) -> Self {
+        Maze {
+            height,
+            width,
+            walls: vec![wall; height * width],
+        }
+    }
+
+    // number of cells along each axis, none on an empty grid
+    fn cells(&self) -> (usize, usize) {
+        (
+            self.width.saturating_sub(1) / 2,
+            self.height.saturating_sub(1) / 2,
+        )
+    }
+
+    fn set_wall(&mut self, x: usize, y: usize, wall: bool) {
+        self.walls[self.width * y + x] = wall;
+    }
+
+    fn cell_index(&self, cx: usize, cy: usize) -> usize {
+        self.cells().0 * cy + cx
+    }
+
+    // adjacent cells of a cell, in cell coordinates
+    fn cell_neighbours(&self, cx: usize, cy: usize) -> Vec<(usize, usize)> {
+        let (cols, rows) = self.cells();
+        let mut neighbours = Vec::with_capacity(4);
+        if cx + 1 < cols {
+            neighbours.push((cx + 1, cy));
+        }
+        if cx > 0 {
+            neighbours.push((cx - 1, cy));
+        }
+        if cy > 0 {
+            neighbours.push((cx, cy - 1));
+        }
+        if cy + 1 < rows {
+            neighbours.push((cx, cy + 1));
+        }
+        neighbours
+    }
+
+    fn open_cell(&mut self, (cx, cy): (usize, usize)) {
+        self.set_wall(2 * cx + 1, 2 * cy + 1, false);
+    }
+
+    // removes the wall between two adjacent cells
+    fn open_passage(&mut self, (ax, ay): (usize, usize), (bx, by): (usize, usize)) {
+        self.set_wall(ax + bx + 1, ay + by + 1, false);
+    }
+
+    fn into_grid<C: Cost>(self) -> Grid<C> {
+        let mut grid = Grid::with_cost_type(self.height, self.width);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.walls[self.width * y + x] {
+                    grid.set_obstacle(x, y);
+                }
+            }
+        }
+        let (cols, rows) = self.cells();
+        if cols > 0 && rows > 0 {
+            grid.set_start(1, 1);
+            grid.set_goal(2 * cols - 1, 2 * rows - 1);
+        }
+        grid
+    }
+}
+
+fn recursive_backtracker(maze: &mut Maze, rng: &mut Rng) {
+    let (cols, rows) = maze.cells();
+    let mut visited = vec![false; cols * rows];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    maze.open_cell((0, 0));
+    while let Some(&current) = stack.last() {
+        let unvisited: Vec<(usize, usize)> = maze
+            .cell_neighbours(current.0, current.1)
+            .into_iter()
+            .filter(|&(cx, cy)| !visited[maze.cell_index(cx, cy)])
+            .collect();
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let next = unvisited[rng.below(unvisited.len())];
+        visited[maze.cell_index(next.0, next.1)] = true;
+        maze.open_cell(next);
+        maze.open_passage(current, next);
+        stack.push(next);
+    }
+}
+
+fn prim(maze: &mut Maze, rng: &mut Rng) {
+    let (cols, rows) = maze.cells();
+    let mut in_maze = vec![false; cols * rows];
+    // edges leading out of the maze, as (inside, outside)
+    let mut frontier = Vec::new();
+    let start = (rng.below(cols), rng.below(rows));
+    in_maze[maze.cell_index(start.0, start.1)] = true;
+    maze.open_cell(start);
+    for next in maze.cell_neighbours(start.0, start.1) {
+        frontier.push((start, next));
+    }
+    while !frontier.is_empty() {
+        let (from, to) = frontier.swap_remove(rng.below(frontier.len()));
+        let to_index = maze.cell_index(to.0, to.1);
+        if in_maze[to_index] {
+            continue;
+        }
+        in_maze[to_index] = true;
+        maze.open_cell(to);
+        maze.open_passage(from, to);
+        for next in maze.cell_neighbours(to.0, to.1) {
+            if !in_maze[maze.cell_index(next.0, next.1)] {
+                frontier.push((to, next));
+            }
+        }
+    }
+}
+
+fn find_set(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+fn kruskal(maze: &mut Maze, rng: &mut Rng) {
+    let (cols, rows) = maze.cells();
+    let mut parents: Vec<usize> = (0..cols * rows).collect();
+    let mut edges = Vec::new();
+    for cy in 0..rows {
+        for cx in 0..cols {
+            maze.open_cell((cx, cy));
+            if cx + 1 < cols {
+                edges.push(((cx, cy), (cx + 1, cy)));
+            }
+            if cy + 1 < rows {
+                edges.push(((cx, cy), (cx, cy + 1)));
+            }
+        }
+    }
+    rng.shuffle(&mut edges);
+    for (a, b) in edges {
+        let set_a = find_set(&mut parents, maze.cell_index(a.0, a.1));
+        let set_b = find_set(&mut parents, maze.cell_index(b.0, b.1));
+        if set_a != set_b {
+            parents[set_a] = set_b;
+            maze.open_passage(a, b);
+        }
+    }
+}
+
+fn recursive_division(maze: &mut Maze, rng: &mut Rng) {
+    let (cols, rows) = maze.cells();
+    for cy in 0..rows {
+        for cx in 0..cols {
+            maze.open_cell((cx, cy));
+            if cx + 1 < cols {
+                maze.open_passage((cx, cy), (cx + 1, cy));
+            }
+            if cy + 1 < rows {
+                maze.open_passage((cx, cy), (cx, cy + 1));
+            }
+        }
+    }
+    // chambers as inclusive ranges of cells: (x0, y0, x1, y1)
+    let mut chambers = vec![(0, 0, cols - 1, rows - 1)];
+    while let Some((x0, y0, x1, y1)) = chambers.pop() {
+        let (span_x, span_y) = (x1 - x0, y1 - y0);
+        if span_x == 0 && span_y == 0 {
+            continue;
+        }
+        let horizontal = match span_y.cmp(&span_x) {
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Equal => rng.below(2) == 0,
+        };
+        if horizontal {
+            // wall below cell row `wall`, with a single gap
+            let wall = y0 + rng.below(span_y);
+            let gap = x0 + rng.below(span_x + 1);
+            for cx in x0..=x1 {
+                if cx != gap {
+                    maze.set_wall(2 * cx + 1, 2 * wall + 2, true);
+                }
+            }
+            chambers.push((x0, y0, x1, wall));
+            chambers.push((x0, wall + 1, x1, y1));
+        } else {
+            let wall = x0 + rng.below(span_x);
+            let gap = y0 + rng.below(span_y + 1);
+            for cy in y0..=y1 {
+                if cy != gap {
+                    maze.set_wall(2 * wall + 2, 2 * cy + 1, true);
+                }
+            }
+            chambers.push((x0, y0, wall, y1));
+            chambers.push((wall + 1, y0, x1, y1));
+        }
+    }
+}
+
+impl Grid {
+    // generates a perfect maze, the same seed always gives the same maze.
+    // the start and goal are placed in opposite corners
+    pub fn maze(height: usize, width: usize, algorithm: &MazeAlgorithm, seed: u64) -> Self {
+        Grid::maze_with_cost_type(height, width, algorithm, seed)
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // like `maze`, measuring costs in `C`
+    pub fn maze_with_cost_type(
+        height: usize,
+        width: usize,
+        algorithm: &MazeAlgorithm,
+        seed: u64,
+    ) -> Self {
+        let mut maze = Maze::new(height, width, true);
+        let (cols, rows) = maze.cells();
+        if cols == 0 || rows == 0 {
+            return maze.into_grid();
+        }
+        let mut rng = Rng::new(seed);
+        match algorithm {
+            MazeAlgorithm::RecursiveBacktracker => recursive_backtracker(&mut maze, &mut rng),
+            MazeAlgorithm::Prim => prim(&mut maze, &mut rng),
+            MazeAlgorithm::Kruskal => kruskal(&mut maze, &mut rng),
+            MazeAlgorithm::RecursiveDivision => recursive_division(&mut maze, &mut rng),
+        }
+        maze.into_grid()
+    }
+}
@@ -0,0 +1,421 @@
+use crate::cost::Cost;
+use crate::position::Position;
+use crate::Grid;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// an axis aligned rectangle of free nodes, which makes it convex
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Region {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Region {
+    fn intersects(&self, x: usize, y: usize, width: usize, height: usize) -> bool {
+        self.x < x + width
+            && x < self.x + self.width
+            && self.y < y + height
+            && y < self.y + self.height
+    }
+
+    fn center(&self) -> (f32, f32) {
+        (
+            self.x as f32 + self.width as f32 / 2.0,
+            self.y as f32 + self.height as f32 / 2.0,
+        )
+    }
+}
+
+// the shared edge between two touching regions, in node units
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Portal {
+    pub regions: [usize; 2],
+    pub ends: [(f32, f32); 2],
+}
+
+impl Portal {
+    fn midpoint(&self) -> (f32, f32) {
+        let [a, b] = self.ends;
+        ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)
+    }
+
+    fn other(&self, region: usize) -> usize {
+        if self.regions[0] == region {
+            self.regions[1]
+        } else {
+            self.regions[0]
+        }
+    }
+}
+
+// the free space of a grid split into rectangles, plus the portals joining them.
+// region ids stay stable across updates, removed ids are left empty and reused
+#[derive(Debug, Clone)]
+pub struct NavMesh {
+    width: usize,
+    height: usize,
+    // side length of the square agents the mesh was built for
+    agent_size: usize,
+    regions: Vec<Option<Region>>,
+    // region covering each node, None for blocked nodes
+    region_of: Vec<Option<usize>>,
+    portals: Vec<Portal>,
+}
+
+type Point = (f32, f32);
+
+// a (left, right) pair of portal ends
+type Edge = (Point, Point);
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+// twice the signed area of the triangle, its sign tells which side of a -> b c is on
+fn triangle_area(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> f32 {
+    (c.0 - a.0) * (b.1 - a.1) - (b.0 - a.0) * (c.1 - a.1)
+}
+
+// "simple stupid funnel": pulls the path tight through a corridor of portals,
+// each given as (left, right) seen in the direction of travel
+fn string_pull(portals: &[Edge]) -> Vec<(f32, f32)> {
+    let mut path = vec![portals[0].0];
+    let (mut apex, mut left, mut right) = (portals[0].0, portals[0].0, portals[0].1);
+    let (mut left_index, mut right_index) = (0, 0);
+    let mut i = 1;
+    while i < portals.len() {
+        let (next_left, next_right) = portals[i];
+        if triangle_area(apex, right, next_right) <= 0.0 {
+            if apex == right || triangle_area(apex, left, next_right) > 0.0 {
+                right = next_right;
+                right_index = i;
+            } else {
+                // the right side crossed over the left, the left corner is on the path
+                path.push(left);
+                apex = left;
+                let apex_index = left_index;
+                (left, right) = (apex, apex);
+                (left_index, right_index) = (apex_index, apex_index);
+                i = apex_index + 1;
+                continue;
+            }
+        }
+        if triangle_area(apex, left, next_left) >= 0.0 {
+            if apex == left || triangle_area(apex, right, next_left) < 0.0 {
+                left = next_left;
+                left_index = i;
+            } else {
+                path.push(right);
+                apex = right;
+                let apex_index = right_index;
+                (left, right) = (apex, apex);
+                (left_index, right_index) = (apex_index, apex_index);
+                i = apex_index + 1;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    let goal = portals[portals.len() - 1].0;
+    if path.last() != Some(&goal) {
+        path.push(goal);
+    }
+    path
+}
+
+struct Open {
+    f_cost: f32,
+    region: usize,
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f_cost
+            .total_cmp(&self.f_cost)
+            .then_with(|| self.region.cmp(&other.region))
+    }
+}
+
+impl NavMesh {
+    pub fn agent_size(&self) -> usize {
+        self.agent_size
+    }
+
+    pub fn regions(&self) -> impl Iterator<Item = (usize, &Region)> {
+        self.regions
+            .iter()
+            .enumerate()
+            .filter_map(|(id, region)| Some((id, region.as_ref()?)))
+    }
+
+    pub fn region(&self, id: usize) -> Option<&Region> {
+        self.regions.get(id)?.as_ref()
+    }
+
+    pub fn portals(&self) -> &[Portal] {
+        &self.portals
+    }
+
+    pub fn region_at(&self, x: usize, y: usize) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.region_of[self.width * y + x]
+    }
+
+    fn region_at_point(&self, point: (f32, f32)) -> Option<usize> {
+        if point.0 < 0.0 || point.1 < 0.0 {
+            return None;
+        }
+        self.region_at(point.0 as usize, point.1 as usize)
+    }
+
+    // greedily covers the free, unassigned nodes inside the rectangle: each
+    // region grows right as far as it can, then down while the whole row fits
+    fn decompose<C: Cost>(
+        &mut self,
+        grid: &Grid<C>,
+        area: (usize, usize, usize, usize),
+    ) -> Vec<usize> {
+        let (left, top, right, bottom) = area;
+        let free = |mesh: &NavMesh, x: usize, y: usize| {
+            mesh.region_of[mesh.width * y + x].is_none()
+                && !grid.is_blocked_for(&Position::new(x as i32, y as i32), mesh.agent_size)
+        };
+        let mut added = Vec::new();
+        for y in top..bottom {
+            for x in left..right {
+                if !free(self, x, y) {
+                    continue;
+                }
+                let mut width = 1;
+                while x + width < right && free(self, x + width, y) {
+                    width += 1;
+                }
+                let mut height = 1;
+                while y + height < bottom && (x..x + width).all(|x| free(self, x, y + height)) {
+                    height += 1;
+                }
+                let region = Region {
+                    x,
+                    y,
+                    width,
+                    height,
+                };
+                let id = match self.regions.iter().position(Option::is_none) {
+                    Some(id) => id,
+                    None => {
+                        self.regions.push(None);
+                        self.regions.len() - 1
+                    }
+                };
+                for y in y..y + height {
+                    for x in x..x + width {
+                        self.region_of[self.width * y + x] = Some(id);
+                    }
+                }
+                self.regions[id] = Some(region);
+                added.push(id);
+            }
+        }
+        added
+    }
+
+    // adds the portals between the given regions and every region they touch
+    fn connect(&mut self, ids: &[usize]) {
+        for &id in ids {
+            let region = self.region(id).expect("region was just added").clone();
+            let mut touching: Vec<(usize, Point, Point)> = Vec::new();
+            for (other_id, other) in self.regions() {
+                if other_id == id || (ids.contains(&other_id) && other_id < id) {
+                    continue; // the pair is handled from the lower id
+                }
+                let (top, bottom) = (
+                    region.y.max(other.y),
+                    (region.y + region.height).min(other.y + other.height),
+                );
+                let (left, right) = (
+                    region.x.max(other.x),
+                    (region.x + region.width).min(other.x + other.width),
+                );
+                let edge = if top < bottom
+                    && (region.x + region.width == other.x || other.x + other.width == region.x)
+                {
+                    let x = if region.x + region.width == other.x {
+                        other.x
+                    } else {
+                        region.x
+                    } as f32;
+                    Some(((x, top as f32), (x, bottom as f32)))
+                } else if left < right
+                    && (region.y + region.height == other.y || other.y + other.height == region.y)
+                {
+                    let y = if region.y + region.height == other.y {
+                        other.y
+                    } else {
+                        region.y
+                    } as f32;
+                    Some(((left as f32, y), (right as f32, y)))
+                } else {
+                    None
+                };
+                if let Some((a, b)) = edge {
+                    touching.push((other_id, a, b));
+                }
+            }
+            self.portals
+                .extend(touching.into_iter().map(|(other_id, a, b)| Portal {
+                    regions: [id, other_id],
+                    ends: [a, b],
+                }));
+        }
+    }
+
+    // rebuilds the part of the mesh around a rectangle of edited nodes. regions
+    // overlapping it are removed and their nodes decomposed again
+    pub fn update<C: Cost>(
+        &mut self,
+        grid: &Grid<C>,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) {
+        assert!(
+            grid.width() == self.width && grid.height() == self.height,
+            "the grid changed size, build a new mesh"
+        );
+        // large agents are blocked by obstacles up to agent_size - 1 nodes away
+        let reach = self.agent_size.saturating_sub(1);
+        let (x, y) = (x.saturating_sub(reach), y.saturating_sub(reach));
+        let (width, height) = (width + reach, height + reach);
+        let (mut left, mut top) = (x, y);
+        let (mut right, mut bottom) = ((x + width).min(self.width), (y + height).min(self.height));
+        let mut removed = Vec::new();
+        for (id, slot) in self.regions.iter_mut().enumerate() {
+            if slot
+                .as_ref()
+                .is_some_and(|region| region.intersects(x, y, width, height))
+            {
+                let region = slot.take().expect("checked above");
+                left = left.min(region.x);
+                top = top.min(region.y);
+                right = right.max(region.x + region.width);
+                bottom = bottom.max(region.y + region.height);
+                removed.push(id);
+            }
+        }
+        for region in self.region_of.iter_mut() {
+            if region.is_some_and(|id| removed.contains(&id)) {
+                *region = None;
+            }
+        }
+        self.portals
+            .retain(|portal| !portal.regions.iter().any(|id| removed.contains(id)));
+        let added = self.decompose(grid, (left, top, right, bottom));
+        self.connect(&added);
+    }
+
+    // a path between two points in node units: A* over the regions through
+    // portal midpoints, then pulled tight with the funnel algorithm. the
+    // corridor is chosen by midpoint distances, so the path can be longer
+    // than the shortest one when another corridor would pull tighter
+    pub fn find_path(&self, from: (f32, f32), to: (f32, f32)) -> Option<Vec<(f32, f32)>> {
+        let start = self.region_at_point(from)?;
+        let goal = self.region_at_point(to)?;
+        let mut g_costs = vec![f32::INFINITY; self.regions.len()];
+        let mut entries = vec![from; self.regions.len()];
+        let mut parents: Vec<Option<usize>> = vec![None; self.regions.len()];
+        g_costs[start] = 0.0;
+        let mut open_set = BinaryHeap::from([Open {
+            f_cost: distance(from, to),
+            region: start,
+        }]);
+        let mut closed = vec![false; self.regions.len()];
+        while let Some(Open { region, .. }) = open_set.pop() {
+            if closed[region] {
+                continue;
+            }
+            closed[region] = true;
+            if region == goal {
+                break;
+            }
+            for (index, portal) in self.portals.iter().enumerate() {
+                if !portal.regions.contains(&region) {
+                    continue;
+                }
+                let next = portal.other(region);
+                let entry = portal.midpoint();
+                let g_cost = g_costs[region] + distance(entries[region], entry);
+                if g_cost < g_costs[next] {
+                    g_costs[next] = g_cost;
+                    entries[next] = entry;
+                    parents[next] = Some(index);
+                    open_set.push(Open {
+                        f_cost: g_cost + distance(entry, to),
+                        region: next,
+                    });
+                }
+            }
+        }
+        if !closed[goal] {
+            return None;
+        }
+
+        // the corridor of portals from the goal back to the start
+        let mut corridor = vec![(to, to)];
+        let mut region = goal;
+        while let Some(index) = parents[region] {
+            let portal = &self.portals[index];
+            let previous = portal.other(region);
+            let (a, b) = (portal.ends[0], portal.ends[1]);
+            // travelling from `previous` into `region`
+            let (center, mid) = (self.regions[previous].as_ref()?.center(), portal.midpoint());
+            let direction = (mid.0 - center.0, mid.1 - center.1);
+            let side = direction.0 * (a.1 - mid.1) - direction.1 * (a.0 - mid.0);
+            corridor.push(if side > 0.0 { (a, b) } else { (b, a) });
+            region = previous;
+        }
+        corridor.push((from, from));
+        corridor.reverse();
+        Some(string_pull(&corridor))
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // splits the space an agent `agent_size` nodes wide can stand in into
+    // rectangles, a region holds the top left nodes of the agent
+    pub fn navmesh(&self, agent_size: usize) -> NavMesh {
+        let mut mesh = NavMesh {
+            width: self.width,
+            height: self.height,
+            agent_size,
+            regions: Vec::new(),
+            region_of: vec![None; self.width * self.height],
+            portals: Vec::new(),
+        };
+        let added = mesh.decompose(self, (0, 0, self.width, self.height));
+        mesh.connect(&added);
+        mesh
+    }
+}
@@ -1,7 +1,9 @@
+use crate::cost::Cost;
 use crate::position::Position;
 use std::cmp::Ordering;
 
 #[derive(PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeType {
     Obstacle,
     Traversable,
@@ -9,49 +11,53 @@ pub enum NodeType {
     Path,
 }
 
-pub struct Node {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Node<C = usize> {
     pub node_type: NodeType,
-    pub h_cost: usize,
-    pub g_cost: usize,
-    pub f_cost: usize,
+    pub h_cost: C,
+    pub g_cost: C,
+    pub f_cost: C,
     pub index: usize,
     pub parent: Option<Position>,
+    // multiplier applied to the cost of stepping onto this node
+    pub terrain_cost: usize,
 }
 
-impl Node {
+impl<C> Node<C> {
     pub fn set_obstacle(&mut self) {
         self.node_type = NodeType::Obstacle
     }
 }
 
-impl Default for Node {
+impl<C: Cost> Default for Node<C> {
     fn default() -> Self {
         Node {
             node_type: NodeType::Traversable,
-            h_cost: usize::MAX,
-            g_cost: usize::MAX,
-            f_cost: usize::MAX,
+            h_cost: C::infinity(),
+            g_cost: C::infinity(),
+            f_cost: C::infinity(),
             index: usize::MAX,
             parent: None,
+            terrain_cost: 1,
         }
     }
 }
 
-impl Eq for Node {}
+impl<C> Eq for Node<C> {}
 
-impl PartialEq<Self> for Node {
+impl<C> PartialEq<Self> for Node<C> {
     fn eq(&self, other: &Self) -> bool {
         self.index == other.index
     }
 }
 
-impl PartialOrd<Self> for Node {
+impl<C: Ord> PartialOrd<Self> for Node<C> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for Node {
+impl<C: Ord> Ord for Node<C> {
     fn cmp(&self, other: &Self) -> Ordering {
         other
             .f_cost
@@ -59,3 +65,40 @@ impl Ord for Node {
             .then_with(|| self.index.cmp(&other.index))
     }
 }
+
+// serialises the node map as a list so that formats with string-only keys (JSON) work
+#[cfg(feature = "serde")]
+pub(crate) mod serde_nodes {
+    use super::Node;
+    use crate::position::Position;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    type Nodes<C> = HashMap<Position, Rc<RefCell<Node<C>>>>;
+
+    pub fn serialize<C: Serialize, S: Serializer>(
+        nodes: &Nodes<C>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut entries: Vec<(&Position, std::cell::Ref<Node<C>>)> = nodes
+            .iter()
+            .map(|(pos, node)| (pos, node.borrow()))
+            .collect();
+        entries.sort_by_key(|(_, node)| node.index);
+        let entries: Vec<(&Position, &Node<C>)> =
+            entries.iter().map(|(pos, node)| (*pos, &**node)).collect();
+        entries.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, C: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Nodes<C>, D::Error> {
+        let entries: Vec<(Position, Node<C>)> = Vec::deserialize(deserializer)?;
+        Ok(entries
+            .into_iter()
+            .map(|(pos, node)| (pos, Rc::new(RefCell::new(node))))
+            .collect())
+    }
+}
@@ -1,29 +1,12 @@
-use std::cmp;
 use std::hash::Hash;
 use std::ops::Add;
 
 #[derive(Hash, Debug, Clone, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub x: i32,
     pub y: i32,
 }
-pub fn euclidean_distance(from: &Position, to: &Position) -> usize {
-    let x_dist = (from.x - to.x).pow(2);
-    let y_dist = (from.y - to.y).pow(2);
-    (f32::sqrt((x_dist + y_dist) as f32) * 10.0) as usize
-}
-
-pub fn manhattan_distance(from: &Position, to: &Position) -> usize {
-    let x_dist = (from.x - to.x).abs();
-    let y_dist = (from.y - to.y).abs();
-    ((x_dist + y_dist) * 10) as usize
-}
-
-pub fn diagonal_distance(from: &Position, to: &Position) -> usize {
-    let x_dist = (from.x - to.x).abs();
-    let y_dist = (from.y - to.y).abs();
-    (cmp::max(x_dist, y_dist) * 10) as usize
-}
 
 impl Position {
     pub const fn new(x: i32, y: i32) -> Self {
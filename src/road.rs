@@ -0,0 +1,384 @@
+use crate::cost::Cost;
+use crate::Grid;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// mean earth radius in metres
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+// DIMACS coordinates are integers in millionths of a degree
+const MICRO_DEGREES: f64 = 1_000_000.0;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseGraphError {
+    MissingProblemLine,
+    DuplicateProblemLine { line: usize },
+    BadLine { line: usize },
+    NodeOutOfRange { line: usize, node: usize },
+    ArcCount { expected: usize, found: usize },
+}
+
+impl fmt::Display for ParseGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseGraphError::MissingProblemLine => write!(f, "file has no problem line"),
+            ParseGraphError::DuplicateProblemLine { line } => {
+                write!(f, "line {line} is a second problem line")
+            }
+            ParseGraphError::BadLine { line } => write!(f, "line {line} is malformed"),
+            ParseGraphError::NodeOutOfRange { line, node } => {
+                write!(f, "node {node} on line {line} is not in the graph")
+            }
+            ParseGraphError::ArcCount { expected, found } => {
+                write!(f, "expected {expected} arcs but found {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseGraphError {}
+
+#[derive(Debug)]
+pub enum GraphFileError {
+    Io(io::Error),
+    Parse(ParseGraphError),
+}
+
+impl fmt::Display for GraphFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphFileError::Io(err) => write!(f, "{err}"),
+            GraphFileError::Parse(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for GraphFileError {}
+
+impl From<io::Error> for GraphFileError {
+    fn from(err: io::Error) -> Self {
+        GraphFileError::Io(err)
+    }
+}
+
+impl From<ParseGraphError> for GraphFileError {
+    fn from(err: ParseGraphError) -> Self {
+        GraphFileError::Parse(err)
+    }
+}
+
+// how the straight line distance between two node coordinates is measured
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GraphHeuristic {
+    // no estimate, the search is plain dijkstra
+    Zero,
+    // coordinates taken as points on a plane
+    Euclidean,
+    // coordinates taken as longitude and latitude, distance along the earth's surface
+    GreatCircle,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Edge {
+    pub to: usize,
+    pub weight: u64,
+}
+
+// a directed graph with integer arc weights, as in the DIMACS shortest path
+// challenge. nodes are numbered from 0, so DIMACS node n is node n - 1 here
+#[derive(Debug, Clone, Default)]
+pub struct RoadGraph {
+    edges: Vec<Vec<Edge>>,
+    coordinates: Vec<Option<(f64, f64)>>,
+    // worked out from every arc on the first estimate after a change
+    scales: OnceLock<Scales>,
+}
+
+// the smallest ratio of arc weight to each geometric distance. scaling
+// distances by it never overestimates an arc, whatever the units are
+#[derive(Debug, Clone, Copy)]
+struct Scales {
+    euclidean: f64,
+    great_circle: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Route {
+    // from the start to the goal, both included
+    pub path: Vec<usize>,
+    pub cost: u64,
+    // how many nodes the search expanded
+    pub expanded: usize,
+}
+
+// the fields after the line's tag, None when there are too many or too few or
+// one is not a number
+fn numbers<T: std::str::FromStr, const N: usize>(line: &str) -> Option<[T; N]> {
+    let fields: Option<Vec<T>> = line
+        .split_whitespace()
+        .skip(1)
+        .map(|field| field.parse().ok())
+        .collect();
+    fields?.try_into().ok()
+}
+
+fn euclidean(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+// haversine distance in metres between two (longitude, latitude) pairs in degrees
+fn great_circle(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lon_a, lat_a) = (a.0.to_radians(), a.1.to_radians());
+    let (lon_b, lat_b) = (b.0.to_radians(), b.1.to_radians());
+    let h = ((lat_b - lat_a) / 2.0).sin().powi(2)
+        + lat_a.cos() * lat_b.cos() * ((lon_b - lon_a) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * h.sqrt().min(1.0).asin()
+}
+
+impl RoadGraph {
+    // an empty graph with `nodes` nodes and no coordinates
+    pub fn new(nodes: usize) -> Self {
+        RoadGraph {
+            edges: vec![Vec::new(); nodes],
+            coordinates: vec![None; nodes],
+            scales: OnceLock::new(),
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum()
+    }
+
+    pub fn edges(&self, node: usize) -> &[Edge] {
+        &self.edges[node]
+    }
+
+    pub fn coordinates(&self, node: usize) -> Option<(f64, f64)> {
+        self.coordinates[node]
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, weight: u64) {
+        assert!(from < self.node_count(), "node {from} is not in the graph");
+        assert!(to < self.node_count(), "node {to} is not in the graph");
+        self.edges[from].push(Edge { to, weight });
+        self.scales.take();
+    }
+
+    pub fn set_coordinates(&mut self, node: usize, x: f64, y: f64) {
+        self.coordinates[node] = Some((x, y));
+        self.scales.take();
+    }
+
+    // parses the arcs of a DIMACS `.gr` file:
+    // `c` comment, `p sp <nodes> <arcs>` problem line, `a <from> <to> <weight>` arc
+    pub fn from_dimacs(gr: &str) -> Result<Self, ParseGraphError> {
+        let mut graph: Option<RoadGraph> = None;
+        let mut expected = 0;
+        let mut found = 0;
+        for (index, line) in gr.lines().enumerate() {
+            let line_number = index + 1;
+            let bad_line = ParseGraphError::BadLine { line: line_number };
+            match line.split_whitespace().next() {
+                None | Some("c") => {}
+                Some("p") => {
+                    if graph.is_some() {
+                        return Err(ParseGraphError::DuplicateProblemLine { line: line_number });
+                    }
+                    let fields: Vec<&str> = line.split_whitespace().collect();
+                    let [_, "sp", nodes, arcs] = fields[..] else {
+                        return Err(bad_line);
+                    };
+                    let nodes = nodes.parse().map_err(|_| bad_line.clone())?;
+                    expected = arcs.parse().map_err(|_| bad_line.clone())?;
+                    graph = Some(RoadGraph::new(nodes));
+                }
+                Some("a") => {
+                    let graph = graph.as_mut().ok_or(ParseGraphError::MissingProblemLine)?;
+                    let [from, to, weight] = numbers::<u64, 3>(line).ok_or(bad_line)?;
+                    let from = graph.dimacs_node(from as usize, line_number)?;
+                    let to = graph.dimacs_node(to as usize, line_number)?;
+                    graph.add_edge(from, to, weight);
+                    found += 1;
+                }
+                Some(_) => return Err(bad_line),
+            }
+        }
+        let graph = graph.ok_or(ParseGraphError::MissingProblemLine)?;
+        if found != expected {
+            return Err(ParseGraphError::ArcCount { expected, found });
+        }
+        Ok(graph)
+    }
+
+    // reads node positions from a DIMACS `.co` file:
+    // `p aux sp co <nodes>` problem line, `v <node> <x> <y>` coordinates. these
+    // are longitude and latitude in millionths of a degree and are stored in degrees
+    pub fn add_dimacs_coordinates(&mut self, co: &str) -> Result<(), ParseGraphError> {
+        let mut seen_problem = false;
+        for (index, line) in co.lines().enumerate() {
+            let line_number = index + 1;
+            let bad_line = ParseGraphError::BadLine { line: line_number };
+            match line.split_whitespace().next() {
+                None | Some("c") => {}
+                Some("p") if seen_problem => {
+                    return Err(ParseGraphError::DuplicateProblemLine { line: line_number })
+                }
+                Some("p") => seen_problem = true,
+                Some("v") => {
+                    if !seen_problem {
+                        return Err(ParseGraphError::MissingProblemLine);
+                    }
+                    let [node, x, y] = numbers::<i64, 3>(line).ok_or(bad_line)?;
+                    let node = self.dimacs_node(node as usize, line_number)?;
+                    self.coordinates[node] =
+                        Some((x as f64 / MICRO_DEGREES, y as f64 / MICRO_DEGREES));
+                }
+                Some(_) => return Err(bad_line),
+            }
+        }
+        if !seen_problem {
+            return Err(ParseGraphError::MissingProblemLine);
+        }
+        self.scales.take();
+        Ok(())
+    }
+
+    // loads a `.gr` file and, when given, the matching `.co` file
+    pub fn load_dimacs(
+        gr: impl AsRef<Path>,
+        co: Option<impl AsRef<Path>>,
+    ) -> Result<Self, GraphFileError> {
+        let mut graph = RoadGraph::from_dimacs(&std::fs::read_to_string(gr)?)?;
+        if let Some(co) = co {
+            graph.add_dimacs_coordinates(&std::fs::read_to_string(co)?)?;
+        }
+        Ok(graph)
+    }
+
+    fn dimacs_node(&self, node: usize, line: usize) -> Result<usize, ParseGraphError> {
+        if node == 0 || node > self.node_count() {
+            return Err(ParseGraphError::NodeOutOfRange { line, node });
+        }
+        Ok(node - 1)
+    }
+
+    // the scales that fit every arc, arcs without coordinates at both ends are ignored
+    fn scales(&self) -> Scales {
+        *self.scales.get_or_init(|| {
+            let mut scales = Scales {
+                euclidean: f64::INFINITY,
+                great_circle: f64::INFINITY,
+            };
+            for (from, edges) in self.edges.iter().enumerate() {
+                for edge in edges {
+                    let (Some(a), Some(b)) = (self.coordinates[from], self.coordinates[edge.to])
+                    else {
+                        continue;
+                    };
+                    let weight = edge.weight as f64;
+                    // nan for zero weight arcs between the same point, which min ignores
+                    scales.euclidean = scales.euclidean.min(weight / euclidean(a, b));
+                    scales.great_circle = scales.great_circle.min(weight / great_circle(a, b));
+                }
+            }
+            scales
+        })
+    }
+
+    // a lower bound on the cost from `from` to `to`, 0 when either has no coordinates
+    pub fn estimate(&self, heuristic: GraphHeuristic, from: usize, to: usize) -> u64 {
+        let (Some(a), Some(b)) = (self.coordinates[from], self.coordinates[to]) else {
+            return 0;
+        };
+        let (distance, scale) = match heuristic {
+            GraphHeuristic::Zero => return 0,
+            GraphHeuristic::Euclidean => (euclidean(a, b), self.scales().euclidean),
+            GraphHeuristic::GreatCircle => (great_circle(a, b), self.scales().great_circle),
+        };
+        if !scale.is_finite() {
+            return 0; // no arc has coordinates at both ends
+        }
+        // a little slack so rounding never makes an estimate too large
+        (distance * scale * (1.0 - 1e-9)).floor() as u64
+    }
+
+    // A* from `start` to `goal`, None when the goal can't be reached
+    pub fn solve(&self, start: usize, goal: usize, heuristic: GraphHeuristic) -> Option<Route> {
+        assert!(
+            start < self.node_count(),
+            "node {start} is not in the graph"
+        );
+        assert!(goal < self.node_count(), "node {goal} is not in the graph");
+        let mut g_costs = vec![u64::MAX; self.node_count()];
+        let mut parents: Vec<Option<usize>> = vec![None; self.node_count()];
+        let mut closed = vec![false; self.node_count()];
+        g_costs[start] = 0;
+        let mut open_set =
+            BinaryHeap::from([Reverse((self.estimate(heuristic, start, goal), start))]);
+        let mut expanded = 0;
+        while let Some(Reverse((_, node))) = open_set.pop() {
+            if closed[node] {
+                continue;
+            }
+            closed[node] = true;
+            if node == goal {
+                let mut path = vec![goal];
+                while let Some(parent) = parents[path[path.len() - 1]] {
+                    path.push(parent);
+                }
+                path.reverse();
+                return Some(Route {
+                    path,
+                    cost: g_costs[goal],
+                    expanded,
+                });
+            }
+            expanded += 1;
+            for edge in &self.edges[node] {
+                let g_cost = g_costs[node] + edge.weight;
+                if g_cost < g_costs[edge.to] {
+                    g_costs[edge.to] = g_cost;
+                    parents[edge.to] = Some(node);
+                    let f_cost = g_cost + self.estimate(heuristic, edge.to, goal);
+                    open_set.push(Reverse((f_cost, edge.to)));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // the grid as a graph where node y * width + x is the grid node (x, y),
+    // placed at its coordinates. arcs are the moves of a 1x1 agent, so blocked
+    // nodes are left without arcs whatever size agent is planned for. arc
+    // weights are integers, so step costs are rounded onto the scale where a
+    // straight step weighs 10, which leaves `usize` costs unchanged
+    pub fn road_graph(&self) -> RoadGraph {
+        let mut graph = RoadGraph::new(self.width * self.height);
+        for index in 0..graph.node_count() {
+            let pos = self.get_pos_from_index(index);
+            graph.coordinates[index] = Some((pos.x as f64, pos.y as f64));
+        }
+        for index in 0..graph.node_count() {
+            let pos = self.get_pos_from_index(index);
+            if self.is_blocked(&pos) {
+                continue;
+            }
+            for (next, cost) in self.get_move_costs(&pos) {
+                let weight = (cost.ratio(C::straight()) * 10.0).round() as u64;
+                graph.add_edge(index, self.get_index_from_pos(&next), weight);
+            }
+        }
+        graph
+    }
+}
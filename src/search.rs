@@ -0,0 +1,217 @@
+use crate::cost::Cost;
+use crate::limits::SearchLimits;
+use crate::position::Position;
+use crate::{HeuristicFn, SolveResult};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+// anything A* can run over: the positions that can be stepped onto from a
+// position, each with the cost of the step
+pub(crate) trait SearchSpace<C> {
+    fn successors(&self, pos: &Position) -> Vec<(Position, C)>;
+}
+
+// the state of one A* search. costs are kept in maps keyed by position, so
+// the space doesn't need to be bounded. the open set is keyed on g + weight * h
+pub(crate) struct Search<'a, C, S> {
+    space: &'a S,
+    goal: Position,
+    heuristic: HeuristicFn<C>,
+    pub g_costs: HashMap<Position, C>,
+    pub h_costs: HashMap<Position, C>,
+    pub parents: HashMap<Position, Position>,
+    open_set: BinaryHeap<Reverse<(C, Position)>>,
+    weight: f64,
+    // anytime searches (ARA*) expand a node at most once per weight, nodes
+    // improved after that wait in `inconsistent` for the next weight. None
+    // when improved nodes are simply reopened
+    closed: Option<HashSet<Position>>,
+    inconsistent: HashSet<Position>,
+    // closest node to the goal seen so far, for partial results
+    best: (C, Position),
+    expanded: usize,
+    #[cfg(not(target_arch = "wasm32"))]
+    started: Instant,
+}
+
+impl<'a, C: Cost, S: SearchSpace<C>> Search<'a, C, S> {
+    pub fn new(space: &'a S, start: Position, goal: Position, heuristic: HeuristicFn<C>) -> Self {
+        let h_cost = heuristic(&start, &goal);
+        Search {
+            space,
+            goal,
+            heuristic,
+            g_costs: HashMap::from([(start.clone(), C::zero())]),
+            h_costs: HashMap::from([(start.clone(), h_cost)]),
+            parents: HashMap::new(),
+            open_set: BinaryHeap::from([Reverse((h_cost, start.clone()))]),
+            weight: 1.0,
+            closed: None,
+            inconsistent: HashSet::new(),
+            best: (h_cost, start),
+            expanded: 0,
+            #[cfg(not(target_arch = "wasm32"))]
+            started: Instant::now(),
+        }
+    }
+
+    // turns the search into ARA*, starting with the heuristic weighted by `weight`
+    pub fn anytime(mut self, weight: f64) -> Self {
+        self.closed = Some(HashSet::new());
+        self.reweight(weight);
+        self
+    }
+
+    // time since the search was created, None on wasm where there is no clock
+    pub fn elapsed(&self) -> Option<Duration> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let elapsed = Some(self.started.elapsed());
+        #[cfg(target_arch = "wasm32")]
+        let elapsed = None;
+        elapsed
+    }
+
+    fn h_cost(&mut self, pos: &Position) -> C {
+        if let Some(&h_cost) = self.h_costs.get(pos) {
+            return h_cost;
+        }
+        let h_cost = (self.heuristic)(pos, &self.goal);
+        self.h_costs.insert(pos.clone(), h_cost);
+        h_cost
+    }
+
+    fn key(&self, pos: &Position) -> C {
+        let h_cost = self.h_costs[pos];
+        let h_cost = if self.weight == 1.0 {
+            h_cost
+        } else {
+            h_cost.scale(self.weight)
+        };
+        self.g_costs[pos].saturating_add(h_cost)
+    }
+
+    fn is_stale(&self, key: C, pos: &Position) -> bool {
+        key != self.key(pos)
+            || self
+                .closed
+                .as_ref()
+                .is_some_and(|closed| closed.contains(pos))
+    }
+
+    pub fn goal_cost(&self) -> C {
+        self.g_costs
+            .get(&self.goal)
+            .copied()
+            .unwrap_or(C::infinity())
+    }
+
+    // expands nodes until nothing left in the open set can improve on the
+    // path to the goal, the open set runs dry or a limit is hit
+    pub fn run(&mut self, limits: &SearchLimits) -> SolveResult {
+        while let Some(Reverse((key, pos))) = self.open_set.peek().cloned() {
+            if self.is_stale(key, &pos) {
+                self.open_set.pop();
+                continue;
+            }
+            let goal_cost = self.goal_cost();
+            if goal_cost != C::infinity() && goal_cost <= key {
+                return SolveResult::Found;
+            }
+            if let Some(reason) = limits.check(self.expanded, self.elapsed()) {
+                return SolveResult::Partial(reason);
+            }
+            self.open_set.pop();
+            self.expanded += 1;
+            if let Some(closed) = self.closed.as_mut() {
+                closed.insert(pos.clone());
+            }
+            self.expand(&pos, self.g_costs[&pos]);
+        }
+        if self.goal_cost() == C::infinity() {
+            SolveResult::Unreachable
+        } else {
+            SolveResult::Found
+        }
+    }
+
+    fn expand(&mut self, pos: &Position, g_cost: C) {
+        for (next, step) in self.space.successors(pos) {
+            let next_g_cost = g_cost.saturating_add(step);
+            if next_g_cost == C::infinity() {
+                continue;
+            }
+            if self
+                .g_costs
+                .get(&next)
+                .is_some_and(|&best| next_g_cost >= best)
+            {
+                continue;
+            }
+            let h_cost = self.h_cost(&next);
+            if h_cost < self.best.0 {
+                self.best = (h_cost, next.clone());
+            }
+            self.g_costs.insert(next.clone(), next_g_cost);
+            self.parents.insert(next.clone(), pos.clone());
+            if self
+                .closed
+                .as_ref()
+                .is_some_and(|closed| closed.contains(&next))
+            {
+                self.inconsistent.insert(next);
+            } else {
+                self.open_set.push(Reverse((self.key(&next), next)));
+            }
+        }
+    }
+
+    // the nodes waiting to be expanded, including the inconsistent ones
+    fn waiting(&self) -> HashSet<Position> {
+        let open = self
+            .open_set
+            .iter()
+            .filter(|Reverse((key, pos))| !self.is_stale(*key, pos));
+        open.map(|Reverse((_, pos))| pos.clone())
+            .chain(self.inconsistent.iter().cloned())
+            .collect()
+    }
+
+    // the smallest unweighted f cost among the nodes still waiting, no path to
+    // the goal can be cheaper. None when nothing is left to expand
+    pub fn lower_bound(&self) -> Option<C> {
+        let f_cost = |pos: &Position| self.g_costs[pos].saturating_add(self.h_costs[pos]);
+        self.waiting().iter().map(f_cost).min()
+    }
+
+    // reopens the inconsistent nodes and re-keys the open set for a new weight
+    pub fn reweight(&mut self, weight: f64) {
+        let waiting = self.waiting();
+        self.weight = weight;
+        self.inconsistent.clear();
+        if let Some(closed) = self.closed.as_mut() {
+            closed.clear();
+        }
+        self.open_set = waiting
+            .into_iter()
+            .map(|pos| Reverse((self.key(&pos), pos)))
+            .collect();
+    }
+
+    // the node closest to the goal reached so far
+    pub fn best(&self) -> &Position {
+        &self.best.1
+    }
+
+    // from the start to `pos`, both included
+    pub fn path_to(&self, pos: &Position) -> Vec<Position> {
+        let mut path = vec![pos.clone()];
+        while let Some(parent) = self.parents.get(&path[path.len() - 1]) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+        path
+    }
+}
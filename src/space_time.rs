@@ -0,0 +1,126 @@
+use crate::cost::Cost;
+use crate::position::Position;
+use crate::{Grid, Heuristic};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimedPath<C = usize> {
+    // every position from the start to the goal with its arrival time,
+    // a repeated position is a wait
+    pub steps: Vec<(Position, usize)>,
+    pub cost: C,
+}
+
+impl<C: Cost> Grid<C> {
+    // blocks the node at a single time step, e.g. a guard passing through
+    pub fn reserve(&mut self, x: usize, y: usize, time: usize) {
+        let pos = Position::new(x as i32, y as i32);
+        assert!(self.is_valid_pos(&pos), "{:?} is invalid", pos);
+        self.reservations.insert((pos, time));
+    }
+
+    // blocks the node for a span of time steps, e.g. a closed door
+    pub fn reserve_span(&mut self, x: usize, y: usize, times: Range<usize>) {
+        for time in times {
+            self.reserve(x, y, time);
+        }
+    }
+
+    pub fn is_reserved(&self, x: usize, y: usize, time: usize) -> bool {
+        let pos = Position::new(x as i32, y as i32);
+        self.reservations.contains(&(pos, time))
+    }
+
+    pub fn clear_reservations(&mut self) {
+        self.reservations.clear();
+    }
+
+    // A* over (position, time): every move or wait takes one time step and
+    // reserved nodes can't be entered at their reserved times, nor swapped
+    // with whoever holds the reservations. the goal only counts once it stays
+    // free for good. None when the start is reserved at time 0, and gives up
+    // after `max_time` steps
+    pub fn solve_timed(&self, heuristic: &Heuristic, max_time: usize) -> Option<TimedPath<C>> {
+        let Some(start_pos) = self.start.clone() else {
+            panic!("no start position");
+        };
+        let Some(goal_pos) = self.goal.clone() else {
+            panic!("no goal position")
+        };
+        if !self.is_connected(&start_pos, &goal_pos)
+            || self.reservations.contains(&(start_pos.clone(), 0))
+        {
+            return None;
+        }
+
+        let heuristic = self.heuristic_function(heuristic);
+        let goal_free_after = self
+            .reservations
+            .iter()
+            .filter(|(pos, _)| *pos == goal_pos)
+            .map(|(_, time)| time + 1)
+            .max()
+            .unwrap_or(0);
+
+        let start = (self.get_index_from_pos(&start_pos), 0);
+        let mut g_costs: HashMap<(usize, usize), C> = HashMap::from([(start, C::zero())]);
+        let mut parents: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        let mut open_set = BinaryHeap::new();
+        open_set.push(Reverse((heuristic(&start_pos, &goal_pos), start)));
+
+        while let Some(Reverse((f_cost, current))) = open_set.pop() {
+            let (index, time) = current;
+            let pos = self.get_pos_from_index(index);
+            let g_cost = g_costs[&current];
+            if f_cost > g_cost.saturating_add(heuristic(&pos, &goal_pos)) {
+                continue; // stale entry
+            }
+            if pos == goal_pos && time >= goal_free_after {
+                let mut steps = vec![(pos, time)];
+                let mut state = current;
+                while let Some(&parent) = parents.get(&state) {
+                    state = parent;
+                    steps.push((self.get_pos_from_index(state.0), state.1));
+                }
+                steps.reverse();
+                return Some(TimedPath {
+                    steps,
+                    cost: g_cost,
+                });
+            }
+            if time >= max_time {
+                continue;
+            }
+
+            let mut moves = self.get_move_costs(&pos);
+            // standing still for a time step costs as much as a straight step
+            moves.push((pos.clone(), C::straight()));
+            for (next_pos, step) in moves {
+                if self.reservations.contains(&(next_pos.clone(), time + 1)) {
+                    continue;
+                }
+                // someone coming the other way over the same edge
+                if self.reservations.contains(&(next_pos.clone(), time))
+                    && self.reservations.contains(&(pos.clone(), time + 1))
+                {
+                    continue;
+                }
+                let next = (self.get_index_from_pos(&next_pos), time + 1);
+                let next_g_cost = g_cost.saturating_add(step);
+                if next_g_cost == C::infinity() {
+                    continue;
+                }
+                if g_costs.get(&next).is_none_or(|&best| next_g_cost < best) {
+                    g_costs.insert(next, next_g_cost);
+                    parents.insert(next, current);
+                    let f_cost = next_g_cost.saturating_add(heuristic(&next_pos, &goal_pos));
+                    open_set.push(Reverse((f_cost, next)));
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,247 @@
+use crate::cost::Cost;
+use crate::rng::Rng;
+use crate::Grid;
+
+const MAX_TERRAIN_COST: usize = 9;
+const NOISE_OCTAVES: u32 = 4;
+
+// builds a grid from a wall mask laid out row by row
+fn grid_from_walls<C: Cost>(height: usize, width: usize, walls: &[bool]) -> Grid<C> {
+    let mut grid = Grid::with_cost_type(height, width);
+    for y in 0..height {
+        for x in 0..width {
+            if walls[width * y + x] {
+                grid.set_obstacle(x, y);
+            }
+        }
+    }
+    grid
+}
+
+// number of walls among the 8 surrounding cells, out of bounds counts as wall
+fn count_wall_neighbours(height: usize, width: usize, walls: &[bool], x: usize, y: usize) -> usize {
+    let mut count = 0;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+            let outside = nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64;
+            if outside || walls[width * ny as usize + nx as usize] {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+// smoothly interpolated random values on a lattice `spacing` cells apart
+struct ValueNoise {
+    columns: usize,
+    values: Vec<f32>,
+    spacing: f32,
+}
+
+impl ValueNoise {
+    fn new(height: usize, width: usize, spacing: f32, rng: &mut Rng) -> Self {
+        let columns = (width as f32 / spacing) as usize + 2;
+        let rows = (height as f32 / spacing) as usize + 2;
+        let values = (0..columns * rows).map(|_| rng.next_f32()).collect();
+        ValueNoise {
+            columns,
+            values,
+            spacing,
+        }
+    }
+
+    fn sample(&self, x: usize, y: usize) -> f32 {
+        let (fx, fy) = (x as f32 / self.spacing, y as f32 / self.spacing);
+        let (x0, y0) = (fx as usize, fy as usize);
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        let (tx, ty) = (smooth(fx - x0 as f32), smooth(fy - y0 as f32));
+        let value = |x: usize, y: usize| self.values[self.columns * y + x];
+        let top = value(x0, y0) + (value(x0 + 1, y0) - value(x0, y0)) * tx;
+        let bottom = value(x0, y0 + 1) + (value(x0 + 1, y0 + 1) - value(x0, y0 + 1)) * tx;
+        top + (bottom - top) * ty
+    }
+}
+
+struct Room {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Room {
+    fn centre(&self) -> (usize, usize) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    // rooms must keep at least one wall between them
+    fn overlaps(&self, other: &Room) -> bool {
+        self.x <= other.x + other.width
+            && other.x <= self.x + self.width
+            && self.y <= other.y + other.height
+            && other.y <= self.y + self.height
+    }
+}
+
+impl Grid {
+    // every node independently becomes an obstacle with the given probability
+    pub fn random_obstacles(height: usize, width: usize, density: f32, seed: u64) -> Self {
+        Grid::random_obstacles_with_cost_type(height, width, density, seed)
+    }
+
+    // cellular automaton caves: random fill followed by smoothing passes,
+    // a node becomes wall when 5 or more of its neighbours are walls
+    pub fn cave(height: usize, width: usize, fill: f32, iterations: usize, seed: u64) -> Self {
+        Grid::cave_with_cost_type(height, width, fill, iterations, seed)
+    }
+
+    // fractal value noise mapped onto terrain costs 1-9, `scale` is the size
+    // in nodes of the largest features
+    pub fn noise_terrain(height: usize, width: usize, scale: f32, seed: u64) -> Self {
+        Grid::noise_terrain_with_cost_type(height, width, scale, seed)
+    }
+
+    // rooms joined in placement order by L-shaped corridors, the start is
+    // placed in the first room and the goal in the last
+    pub fn dungeon(height: usize, width: usize, max_rooms: usize, seed: u64) -> Self {
+        Grid::dungeon_with_cost_type(height, width, max_rooms, seed)
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // like `random_obstacles`, measuring costs in `C`
+    pub fn random_obstacles_with_cost_type(
+        height: usize,
+        width: usize,
+        density: f32,
+        seed: u64,
+    ) -> Self {
+        let mut rng = Rng::new(seed);
+        let walls: Vec<bool> = (0..height * width).map(|_| rng.chance(density)).collect();
+        grid_from_walls(height, width, &walls)
+    }
+
+    // like `cave`, measuring costs in `C`
+    pub fn cave_with_cost_type(
+        height: usize,
+        width: usize,
+        fill: f32,
+        iterations: usize,
+        seed: u64,
+    ) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut walls: Vec<bool> = (0..height * width).map(|_| rng.chance(fill)).collect();
+        for _ in 0..iterations {
+            walls = (0..height * width)
+                .map(|index| {
+                    let (x, y) = (index % width, index / width);
+                    let count = count_wall_neighbours(height, width, &walls, x, y);
+                    count >= 5 || (walls[index] && count == 4)
+                })
+                .collect();
+        }
+        grid_from_walls(height, width, &walls)
+    }
+
+    // like `noise_terrain`, measuring costs in `C`
+    pub fn noise_terrain_with_cost_type(
+        height: usize,
+        width: usize,
+        scale: f32,
+        seed: u64,
+    ) -> Self {
+        assert!(scale >= 1.0, "scale must be at least one node");
+        let mut rng = Rng::new(seed);
+        let octaves: Vec<ValueNoise> = (0..NOISE_OCTAVES)
+            .map(|octave| {
+                let spacing = (scale / 2u32.pow(octave) as f32).max(1.0);
+                ValueNoise::new(height, width, spacing, &mut rng)
+            })
+            .collect();
+        let total_weight: f32 = (0..NOISE_OCTAVES).map(|o| 0.5f32.powi(o as i32)).sum();
+
+        let mut grid = Grid::with_cost_type(height, width);
+        for y in 0..height {
+            for x in 0..width {
+                let noise: f32 = octaves
+                    .iter()
+                    .enumerate()
+                    .map(|(o, octave)| octave.sample(x, y) * 0.5f32.powi(o as i32))
+                    .sum::<f32>()
+                    / total_weight;
+                let cost = 1 + (noise * MAX_TERRAIN_COST as f32) as usize;
+                grid.set_terrain_cost(x, y, cost.min(MAX_TERRAIN_COST));
+            }
+        }
+        grid
+    }
+
+    // like `dungeon`, measuring costs in `C`
+    pub fn dungeon_with_cost_type(
+        height: usize,
+        width: usize,
+        max_rooms: usize,
+        seed: u64,
+    ) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut walls = vec![true; height * width];
+        let mut rooms: Vec<Room> = Vec::new();
+        let max_size = (width.min(height) / 3).max(3);
+        // a fixed number of attempts keeps generation bounded on crowded maps
+        for _ in 0..max_rooms * 8 {
+            if rooms.len() == max_rooms || width < 5 || height < 5 {
+                break;
+            }
+            let room_width = 3 + rng.below(max_size - 2);
+            let room_height = 3 + rng.below(max_size - 2);
+            if room_width + 2 > width || room_height + 2 > height {
+                continue;
+            }
+            let room = Room {
+                x: 1 + rng.below(width - room_width - 1),
+                y: 1 + rng.below(height - room_height - 1),
+                width: room_width,
+                height: room_height,
+            };
+            if rooms.iter().any(|other| room.overlaps(other)) {
+                continue;
+            }
+            for y in room.y..room.y + room.height {
+                for x in room.x..room.x + room.width {
+                    walls[width * y + x] = false;
+                }
+            }
+            if let Some(previous) = rooms.last() {
+                let (from_x, from_y) = previous.centre();
+                let (to_x, to_y) = room.centre();
+                let horizontal_first = rng.below(2) == 0;
+                let corner = if horizontal_first {
+                    (to_x, from_y)
+                } else {
+                    (from_x, to_y)
+                };
+                for x in from_x.min(to_x)..=from_x.max(to_x) {
+                    walls[width * corner.1 + x] = false;
+                }
+                for y in from_y.min(to_y)..=from_y.max(to_y) {
+                    walls[width * y + corner.0] = false;
+                }
+            }
+            rooms.push(room);
+        }
+
+        let mut grid = grid_from_walls(height, width, &walls);
+        if let (Some(first), Some(last)) = (rooms.first(), rooms.last()) {
+            let (start_x, start_y) = first.centre();
+            let (goal_x, goal_y) = last.centre();
+            grid.set_start(start_x, start_y);
+            grid.set_goal(goal_x, goal_y);
+        }
+        grid
+    }
+}
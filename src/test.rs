@@ -82,3 +82,1448 @@ fn correct_neighbours_rect() {
         grid.get_neighbours(&Position::new(1, 1))
     );
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    let mut grid = Grid::new(5, 5);
+    grid.set_obstacle(2, 1);
+    grid.set_obstacle(2, 2);
+    grid.set_start(0, 2);
+    grid.set_goal(4, 2);
+    grid.allow_diagonal = false;
+    grid.solve(&Heuristic::Manhattan);
+
+    let json = serde_json::to_string(&grid).unwrap();
+    let restored: Grid = serde_json::from_str(&json).unwrap();
+    assert_eq!(grid.start, restored.start);
+    assert_eq!(grid.goal, restored.goal);
+    assert_eq!(grid.path, restored.path);
+    assert!(!restored.allow_diagonal);
+    assert!(restored.get_node_at(2, 1).node_type == NodeType::Obstacle);
+    assert_eq!(
+        grid.get_node_at(0, 2).f_cost,
+        restored.get_node_at(0, 2).f_cost
+    );
+
+    let heuristic: Heuristic = serde_json::from_str("\"Diagonal\"").unwrap();
+    assert_eq!(Heuristic::Diagonal, heuristic);
+
+    let routes = grid.k_shortest_paths(2, &Heuristic::Manhattan);
+    let json = serde_json::to_string(&routes).unwrap();
+    let restored: Vec<crate::alternatives::Alternative> = serde_json::from_str(&json).unwrap();
+    assert_eq!(routes, restored);
+
+    let route = grid.solve_waypoints(&[(0, 0)], &Heuristic::Manhattan);
+    let json = serde_json::to_string(&route).unwrap();
+    let restored: Option<crate::waypoints::WaypointRoute> = serde_json::from_str(&json).unwrap();
+    assert_eq!(route, restored);
+}
+
+#[test]
+fn parse_ascii_map() {
+    let grid: Grid = "
+        S.#
+        .3#
+        ..G
+    "
+    .parse()
+    .unwrap();
+    assert_eq!((3, 3), (grid.width, grid.height));
+    assert!(grid.is_start(0, 0));
+    assert!(grid.is_goal(2, 2));
+    assert!(grid.get_node_at(2, 0).node_type == NodeType::Obstacle);
+    assert_eq!(3, grid.get_node_at(1, 1).terrain_cost);
+    assert_eq!("S.#\n.3#\n..G\n", grid.to_string());
+}
+
+#[test]
+fn parse_ascii_map_errors() {
+    use crate::ascii::ParseGridError;
+    assert_eq!(Err(ParseGridError::Empty), "  ".parse::<Grid>().map(|_| ()));
+    assert_eq!(
+        Err(ParseGridError::RaggedRow { row: 1 }),
+        "S..\n.G".parse::<Grid>().map(|_| ())
+    );
+    assert_eq!(
+        Err(ParseGridError::UnknownChar {
+            ch: 'x',
+            x: 1,
+            y: 0
+        }),
+        "Sx.\n..G".parse::<Grid>().map(|_| ())
+    );
+    assert_eq!(
+        Err(ParseGridError::DuplicateStart),
+        "S.S\n..G".parse::<Grid>().map(|_| ())
+    );
+}
+
+#[test]
+fn display_solved_path() {
+    let mut grid: Grid = "S9G\n.1.".parse().unwrap();
+    grid.allow_diagonal = false;
+    grid.solve(&Heuristic::Manhattan);
+    // the expensive terrain is visited but the path goes around it
+    assert_eq!("SoG\n***\n", grid.to_string());
+
+    // a goal on the start is reached without moving
+    grid.set_goal(0, 0);
+    assert_eq!(SolveResult::Found, grid.solve(&Heuristic::Manhattan));
+    assert_eq!(Some(&[Position::new(0, 0)][..]), grid.path());
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn load_image_map() {
+    use crate::bitmap::{ImageLegend, GOAL_COLOR, START_COLOR};
+    use image::{DynamicImage, Rgb, RgbImage};
+    let mut image = RgbImage::from_pixel(3, 2, Rgb([255, 255, 255]));
+    image.put_pixel(0, 0, START_COLOR);
+    image.put_pixel(2, 0, GOAL_COLOR);
+    image.put_pixel(1, 0, Rgb([0, 0, 0]));
+    image.put_pixel(1, 1, Rgb([128, 128, 128]));
+    let grid: Grid =
+        Grid::from_image(&DynamicImage::ImageRgb8(image), &ImageLegend::default()).unwrap();
+    assert_eq!("S#G\n.9.\n", grid.to_string());
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn export_image_round_trip() {
+    use crate::bitmap::{ImageLegend, PATH_COLOR};
+    use image::DynamicImage;
+    let mut grid: Grid = "S.G\n#5#".parse().unwrap();
+    let unsolved = grid.to_image();
+    let restored: Grid =
+        Grid::from_image(&DynamicImage::ImageRgb8(unsolved), &ImageLegend::default()).unwrap();
+    assert_eq!(grid.to_string(), restored.to_string());
+
+    grid.solve(&Heuristic::Manhattan);
+    let solved = grid.to_image();
+    assert_eq!(&PATH_COLOR, solved.get_pixel(1, 0));
+    // the path is read back as open ground, not as a wall
+    let restored: Grid =
+        Grid::from_image(&DynamicImage::ImageRgb8(solved), &ImageLegend::default()).unwrap();
+    assert_eq!("S.G\n#5#\n", restored.to_string());
+}
+
+// counts the open nodes reachable from `from` with 4-way moves
+fn count_reachable(grid: &Grid, from: Position) -> usize {
+    let mut seen = std::collections::HashSet::from([from.clone()]);
+    let mut queue = vec![from];
+    while let Some(pos) = queue.pop() {
+        for next in grid.get_neighbours(&pos) {
+            if grid.nodes[&next].borrow().node_type != NodeType::Obstacle
+                && seen.insert(next.clone())
+            {
+                queue.push(next);
+            }
+        }
+    }
+    seen.len()
+}
+
+#[test]
+fn mazes_are_perfect() {
+    use crate::maze::MazeAlgorithm;
+    for algorithm in [
+        MazeAlgorithm::RecursiveBacktracker,
+        MazeAlgorithm::Prim,
+        MazeAlgorithm::Kruskal,
+        MazeAlgorithm::RecursiveDivision,
+    ] {
+        let grid = Grid::maze(11, 16, &algorithm, 7);
+        let open = grid
+            .nodes
+            .values()
+            .filter(|node| node.borrow().node_type != NodeType::Obstacle)
+            .count();
+        // 7x5 cells joined by a spanning tree of 34 passages
+        assert_eq!(35 + 34, open, "{algorithm:?}");
+        assert_eq!(
+            open,
+            count_reachable(&grid, Position::new(1, 1)),
+            "{algorithm:?}"
+        );
+        assert!(grid.is_start(1, 1) && grid.is_goal(13, 9));
+        assert_eq!(
+            grid.to_string(),
+            Grid::maze(11, 16, &algorithm, 7).to_string()
+        );
+
+        // too small for a single cell
+        for (height, width) in [(0, 0), (0, 5), (2, 2)] {
+            let grid = Grid::maze(height, width, &algorithm, 7);
+            assert_eq!((height, width), (grid.height(), grid.width()));
+            assert!(!grid.is_ready());
+        }
+    }
+}
+
+#[test]
+fn seeded_terrain_is_reproducible() {
+    let generators: [fn(u64) -> Grid; 4] = [
+        |seed| Grid::random_obstacles(20, 30, 0.3, seed),
+        |seed| Grid::cave(20, 30, 0.45, 4, seed),
+        |seed| Grid::noise_terrain(20, 30, 8.0, seed),
+        |seed| Grid::dungeon(20, 30, 6, seed),
+    ];
+    for generate in generators {
+        assert_eq!(generate(3).to_string(), generate(3).to_string());
+        assert_ne!(generate(3).to_string(), generate(4).to_string());
+    }
+}
+
+#[test]
+fn random_obstacle_density() {
+    let grid = Grid::random_obstacles(50, 50, 0.25, 1);
+    let obstacles = grid
+        .nodes
+        .values()
+        .filter(|node| node.borrow().node_type == NodeType::Obstacle)
+        .count();
+    assert!((500..750).contains(&obstacles), "{obstacles}");
+}
+
+#[test]
+fn noise_terrain_costs_in_range() {
+    let grid = Grid::noise_terrain(30, 30, 10.0, 9);
+    let costs: Vec<usize> = grid
+        .nodes
+        .values()
+        .map(|node| node.borrow().terrain_cost)
+        .collect();
+    assert!(costs.iter().all(|cost| (1..=9).contains(cost)));
+    assert!(costs.iter().min() < costs.iter().max());
+}
+
+#[test]
+fn dungeon_rooms_are_connected() {
+    let mut grid = Grid::dungeon(30, 40, 8, 5);
+    assert!(grid.is_ready());
+    let start = grid.start.clone().unwrap();
+    let open = grid
+        .nodes
+        .values()
+        .filter(|node| node.borrow().node_type != NodeType::Obstacle)
+        .count();
+    assert_eq!(open, count_reachable(&grid, start));
+    grid.solve(&Heuristic::Diagonal);
+    assert!(grid.path.is_some());
+}
+
+#[test]
+fn components_follow_new_obstacles() {
+    let mut grid: Grid = "
+        S....
+        ###..
+        G....
+    "
+    .parse()
+    .unwrap();
+    grid.allow_diagonal = false;
+    assert!(grid.is_goal_reachable());
+    assert_eq!(grid.component_at(0, 0), grid.component_at(0, 2));
+    assert_eq!(None, grid.component_at(0, 1));
+
+    grid.set_obstacle(3, 1);
+    assert!(grid.is_goal_reachable());
+    grid.set_obstacle(4, 1);
+    assert!(!grid.is_goal_reachable());
+    assert_ne!(grid.component_at(0, 0), grid.component_at(0, 2));
+
+    // edits and endpoints off the grid leave the cached labels alone
+    grid.set_obstacle(10, 10);
+    assert_ne!(grid.component_at(0, 0), grid.component_at(0, 2));
+    grid.set_goal(10, 10);
+    assert!(!grid.is_goal_reachable());
+
+    // a diagonal step squeezes between the walls
+    let mut grid: Grid = "S#\n#G".parse().unwrap();
+    assert!(grid.is_goal_reachable());
+    grid.allow_diagonal = false;
+    assert!(!grid.is_goal_reachable());
+}
+
+#[test]
+fn unreachable_goal_is_rejected_without_searching() {
+    let mut grid: Grid = "
+        S.#..
+        ..#.G
+        ..#..
+    "
+    .parse()
+    .unwrap();
+    assert_eq!(SolveResult::Unreachable, grid.solve(&Heuristic::Manhattan));
+    assert_eq!(None, grid.path);
+    assert!(grid
+        .nodes
+        .values()
+        .all(|node| node.borrow().node_type != NodeType::Traversed));
+
+    let mut grid: Grid = "S.G".parse().unwrap();
+    assert_eq!(SolveResult::Found, grid.solve(&Heuristic::Manhattan));
+}
+
+#[test]
+fn flow_field_costs_match_solve() {
+    let map = "
+        S.3..
+        .#3#.
+        ..1.G
+    ";
+    let mut grid: Grid = map.parse().unwrap();
+    let field = grid.flow_field(&[(4, 2)]);
+    grid.solve(&Heuristic::Diagonal);
+    assert_eq!(Some(grid.get_node_at(4, 2).g_cost), field.cost_at(0, 0));
+    assert_eq!(Some(0), field.cost_at(4, 2));
+    assert_eq!(None, field.cost_at(1, 1));
+    assert_eq!(Some(Position::new(1, 1)), field.direction_at(3, 0));
+
+    let path = field.path_from(0, 0).unwrap();
+    assert_eq!(Some(&Position::new(0, 0)), path.first());
+    assert_eq!(Some(&Position::new(4, 2)), path.last());
+}
+
+#[test]
+fn flow_field_uses_nearest_goal() {
+    let grid: Grid = ".....\n.....".parse().unwrap();
+    let field = grid.flow_field(&[(0, 0), (4, 0)]);
+    assert_eq!(Some(20), field.cost_at(2, 0));
+    assert_eq!(Some(10), field.cost_at(4, 1));
+    assert_eq!(Some(Position::new(1, -1)), field.direction_at(3, 1));
+}
+
+#[test]
+fn node_budget_returns_partial_path() {
+    use crate::limits::{SearchLimits, StopReason};
+    let mut grid: Grid = "S........G".parse().unwrap();
+    let limits = SearchLimits {
+        max_expanded: Some(3),
+        ..Default::default()
+    };
+    let result = grid.solve_with_limits(&Heuristic::Manhattan, &limits);
+    assert_eq!(SolveResult::Partial(StopReason::NodeBudget), result);
+    // three expansions reach three nodes past the start
+    assert_eq!(Some(&Position::new(3, 0)), grid.path().unwrap().first());
+    assert_eq!(Some(&Position::new(0, 0)), grid.path().unwrap().last());
+    assert_eq!("S***.....G\n", grid.to_string());
+}
+
+#[test]
+fn cancelled_and_timed_out_searches_stop() {
+    use crate::limits::{CancelToken, SearchLimits, StopReason};
+    use std::time::Duration;
+    let mut grid = Grid::new(20, 20);
+    grid.set_start(0, 0);
+    grid.set_goal(19, 19);
+
+    let cancel = CancelToken::new();
+    let remote = cancel.clone();
+    std::thread::spawn(move || remote.cancel()).join().unwrap();
+    let limits = SearchLimits {
+        cancel: Some(cancel),
+        ..Default::default()
+    };
+    let result = grid.solve_with_limits(&Heuristic::Diagonal, &limits);
+    assert_eq!(SolveResult::Partial(StopReason::Cancelled), result);
+    assert_eq!(Some(&[Position::new(0, 0)][..]), grid.path());
+
+    let mut grid = Grid::new(20, 20);
+    grid.set_start(0, 0);
+    grid.set_goal(19, 19);
+    let limits = SearchLimits {
+        max_duration: Some(Duration::ZERO),
+        ..Default::default()
+    };
+    let result = grid.solve_with_limits(&Heuristic::Diagonal, &limits);
+    assert_eq!(SolveResult::Partial(StopReason::TimeBudget), result);
+}
+
+#[test]
+fn anytime_search_converges_to_optimal() {
+    use crate::anytime::AnytimeSettings;
+    use crate::limits::SearchLimits;
+    let mut grid = Grid::noise_terrain(25, 25, 3.0, 4);
+    grid.set_start(0, 0);
+    grid.set_goal(24, 20);
+    let optimal = grid.flow_field(&[(24, 20)]).cost_at(0, 0).unwrap();
+
+    let solutions = grid.solve_anytime(
+        &Heuristic::Diagonal,
+        &AnytimeSettings::default(),
+        &SearchLimits::default(),
+    );
+    assert!(solutions.len() > 1);
+    for pair in solutions.windows(2) {
+        assert!(pair[1].cost < pair[0].cost);
+        assert!(pair[1].bound < pair[0].bound);
+    }
+    for solution in &solutions {
+        assert!(solution.cost as f64 <= solution.bound * optimal as f64);
+        assert_eq!(Some(&Position::new(0, 0)), solution.path.first());
+        assert_eq!(Some(&Position::new(24, 20)), solution.path.last());
+    }
+    let best = solutions.last().unwrap();
+    assert_eq!(1.0, best.bound);
+    assert_eq!(optimal, best.cost);
+
+    // a step that can't lower epsilon still finishes with the optimal path
+    let settings = AnytimeSettings {
+        initial_epsilon: 3.0,
+        epsilon_step: 1e-300,
+    };
+    let solutions = grid.solve_anytime(&Heuristic::Diagonal, &settings, &SearchLimits::default());
+    assert_eq!(
+        Some(optimal),
+        solutions.last().map(|solution| solution.cost)
+    );
+}
+
+#[test]
+fn anytime_search_respects_limits() {
+    use crate::anytime::AnytimeSettings;
+    use crate::limits::SearchLimits;
+    let mut grid = Grid::new(30, 30);
+    grid.set_start(0, 0);
+    grid.set_goal(29, 29);
+    let limits = SearchLimits {
+        max_expanded: Some(5),
+        ..Default::default()
+    };
+    let settings = AnytimeSettings::default();
+    assert!(grid
+        .solve_anytime(&Heuristic::Diagonal, &settings, &limits)
+        .is_empty());
+}
+
+#[test]
+fn clearance_values() {
+    let grid: Grid = "
+        ....
+        ..#.
+        ....
+    "
+    .parse()
+    .unwrap();
+    assert_eq!(0, grid.clearance_at(2, 1));
+    assert_eq!(2, grid.clearance_at(0, 0));
+    assert_eq!(1, grid.clearance_at(1, 0));
+    assert_eq!(1, grid.clearance_at(3, 2));
+    assert_eq!(2, grid.clearance_at(0, 1));
+}
+
+#[test]
+fn large_agents_need_room() {
+    let mut grid: Grid = "
+        S.........
+        ..........
+        ####.##..#
+        ..........
+        .........G
+    "
+    .parse()
+    .unwrap();
+    grid.allow_diagonal = false;
+    assert_eq!(SolveResult::Found, grid.solve(&Heuristic::Manhattan));
+    assert!(grid.path().unwrap().contains(&Position::new(4, 2)));
+
+    grid.set_goal(8, 3);
+    assert_eq!(
+        SolveResult::Found,
+        grid.solve_for_agent(&Heuristic::Manhattan, 2)
+    );
+    assert!(grid
+        .path()
+        .unwrap()
+        .iter()
+        .all(|pos| grid.clearance_at(pos.x as usize, pos.y as usize) >= 2));
+
+    // closing the wide gap leaves no room for the agent
+    grid.set_obstacle(8, 2);
+    assert!(!grid.is_goal_reachable_for_agent(2));
+    assert!(grid.is_goal_reachable());
+}
+
+#[test]
+fn turn_penalty_prefers_straight_paths() {
+    use crate::heading::{Heading, TurnSettings};
+    let mut grid = Grid::new(5, 5);
+    grid.allow_diagonal = false;
+    grid.set_start(0, 0);
+    grid.set_goal(4, 4);
+    let settings = TurnSettings {
+        turn_penalty: 5,
+        ..Default::default()
+    };
+    let path = grid
+        .solve_with_heading(&Heuristic::Manhattan, &settings)
+        .unwrap();
+    let turns = path
+        .steps
+        .windows(2)
+        .filter(|pair| pair[0].1.is_some() && pair[0].1 != pair[1].1)
+        .count();
+    assert_eq!(1, turns);
+    // eight steps and a single 90 degree turn
+    assert_eq!(80 + 2 * 5, path.cost);
+    assert_eq!(Heading::East.turns_to(Heading::West), 4);
+    assert_eq!(Heading::North.turns_to(Heading::NorthWest), 1);
+}
+
+#[test]
+fn forbidden_u_turns() {
+    use crate::heading::{Heading, TurnSettings};
+    let mut grid: Grid = "G.S..".parse().unwrap();
+    let mut settings = TurnSettings {
+        turn_penalty: 3,
+        forbid_u_turns: true,
+        start_heading: Some(Heading::East),
+    };
+    assert_eq!(
+        None,
+        grid.solve_with_heading(&Heuristic::Manhattan, &settings)
+    );
+
+    settings.forbid_u_turns = false;
+    let path = grid
+        .solve_with_heading(&Heuristic::Manhattan, &settings)
+        .unwrap();
+    assert_eq!(20 + 4 * 3, path.cost);
+
+    // with room to manoeuvre the agent can turn around without a u-turn
+    grid = "G.S..\n.....".parse().unwrap();
+    settings.forbid_u_turns = true;
+    assert!(grid
+        .solve_with_heading(&Heuristic::Manhattan, &settings)
+        .is_some());
+}
+
+#[test]
+fn timed_search_waits_for_door() {
+    let mut grid: Grid = "
+        S...G
+        ##.##
+    "
+    .parse()
+    .unwrap();
+    grid.allow_diagonal = false;
+    // the door in the corridor is closed until time 4
+    grid.reserve_span(2, 0, 0..4);
+    assert!(grid.is_reserved(2, 0, 3) && !grid.is_reserved(2, 0, 4));
+    let path = grid.solve_timed(&Heuristic::Manhattan, 50).unwrap();
+    assert_eq!(
+        Some(&(Position::new(2, 0), 4)),
+        path.steps.iter().find(|(pos, _)| pos.x == 2 && pos.y == 0)
+    );
+    assert_eq!(Some(&(Position::new(4, 0), 6)), path.steps.last());
+    // four moves and two waits
+    assert_eq!(60, path.cost);
+    for pair in path.steps.windows(2) {
+        assert_eq!(pair[0].1 + 1, pair[1].1);
+    }
+}
+
+#[test]
+fn timed_search_avoids_guard_on_goal() {
+    let mut grid: Grid = "S.G".parse().unwrap();
+    grid.reserve(2, 0, 5);
+    let path = grid.solve_timed(&Heuristic::Manhattan, 20).unwrap();
+    assert!(path.steps.last().unwrap().1 > 5);
+    assert!(path.steps.iter().all(|(pos, time)| !grid.is_reserved(
+        pos.x as usize,
+        pos.y as usize,
+        *time
+    )));
+
+    grid.reserve_span(1, 0, 0..30);
+    assert_eq!(None, grid.solve_timed(&Heuristic::Manhattan, 20));
+}
+
+#[test]
+fn timed_search_rejects_head_on_swaps() {
+    let mut grid: Grid = "
+        S.G
+        ...
+    "
+    .parse()
+    .unwrap();
+    grid.allow_diagonal = false;
+    // another agent steps from (1, 0) onto the start as we leave it
+    grid.reserve(1, 0, 0);
+    grid.reserve(0, 0, 1);
+    let path = grid.solve_timed(&Heuristic::Manhattan, 20).unwrap();
+    assert_ne!((Position::new(1, 0), 1), path.steps[1]);
+    assert_eq!(Some(&(Position::new(2, 0), 4)), path.steps.last());
+    assert_eq!(40, path.cost);
+
+    // the start is taken before we get going
+    grid.reserve(0, 0, 0);
+    assert_eq!(None, grid.solve_timed(&Heuristic::Manhattan, 20));
+}
+
+#[test]
+fn dubins_paths_reach_the_goal_pose() {
+    use crate::hybrid::{DubinsPath, Pose};
+    use std::f32::consts::PI;
+    let mut rng = crate::rng::Rng::new(11);
+    for _ in 0..200 {
+        let mut pose = || {
+            Pose::new(
+                rng.next_f32() * 20.0,
+                rng.next_f32() * 20.0,
+                rng.next_f32() * 2.0 * PI,
+            )
+        };
+        let (start, goal) = (pose(), pose());
+        let path = DubinsPath::shortest(start, goal, 1.5);
+        let end = path.pose_at(path.length());
+        let straight = ((goal.x - start.x).powi(2) + (goal.y - start.y).powi(2)).sqrt();
+        assert!(path.length() >= straight - 1e-3);
+        assert!((end.x - goal.x).abs() < 1e-2, "{start:?} {goal:?} {end:?}");
+        assert!((end.y - goal.y).abs() < 1e-2, "{start:?} {goal:?} {end:?}");
+        let angle = (end.theta - goal.theta).rem_euclid(2.0 * PI);
+        assert!(angle.min(2.0 * PI - angle) < 1e-2);
+    }
+}
+
+#[test]
+fn hybrid_search_avoids_obstacles() {
+    use crate::hybrid::{HybridSettings, Pose};
+    use std::f32::consts::PI;
+    let grid: Grid = "
+        ....................
+        ....................
+        ....................
+        .........#..........
+        .........#..........
+        .........#..........
+        .........#..........
+        .........#..........
+        .........#..........
+        ....................
+        ....................
+    "
+    .parse()
+    .unwrap();
+    let start = Pose::new(2.5, 5.5, 0.0);
+    let goal = Pose::new(17.5, 5.5, PI / 2.0);
+    let path = grid
+        .solve_hybrid(start, goal, &HybridSettings::default())
+        .unwrap();
+    assert_eq!(Some(&start), path.poses.first());
+    assert_eq!(Some(&goal), path.poses.last());
+    assert!(path.poses.iter().all(|pose| grid.is_pose_free(pose)));
+    for pair in path.poses.windows(2) {
+        let gap = ((pair[1].x - pair[0].x).powi(2) + (pair[1].y - pair[0].y).powi(2)).sqrt();
+        assert!(gap <= 0.5, "{pair:?}");
+    }
+    assert!(path.length > 15.0);
+
+    let blocked = Pose::new(9.5, 5.5, 0.0);
+    assert_eq!(
+        None,
+        grid.solve_hybrid(start, blocked, &HybridSettings::default())
+    );
+}
+
+#[test]
+fn landmark_heuristic_is_admissible() {
+    let assert_admissible = |grid: &Grid| {
+        let goal = Position::new(12, 9);
+        let field = grid.flow_field(&[(12, 9)]);
+        let heuristic = grid.heuristic_function(&Heuristic::Landmark);
+        for y in 0..15 {
+            for x in 0..15 {
+                if let Some(cost) = field.cost_at(x, y) {
+                    let pos = Position::new(x as i32, y as i32);
+                    assert!(heuristic(&pos, &goal) <= cost, "({x}, {y})");
+                }
+            }
+        }
+    };
+    let mut grid = Grid::noise_terrain(15, 15, 4.0, 3);
+    grid.set_obstacle(7, 3);
+    grid.compute_landmarks(6);
+    assert_eq!(6, grid.landmark_positions().len());
+    assert_admissible(&grid);
+
+    // a cheap road would make the old landmark distances overestimate
+    for x in 0..15 {
+        grid.set_terrain_cost(x, 6, 1);
+    }
+    assert_admissible(&grid);
+}
+
+#[test]
+fn landmark_solve_is_optimal() {
+    use crate::maze::MazeAlgorithm;
+    let mut grid = Grid::maze(21, 21, &MazeAlgorithm::Prim, 5);
+    let optimal = grid.flow_field(&[(19, 19)]).cost_at(1, 1).unwrap();
+    assert_eq!(SolveResult::Found, grid.solve(&Heuristic::Landmark));
+    assert_eq!(optimal, grid.get_node_at(19, 19).g_cost);
+}
+
+#[test]
+fn heuristic_checker_finds_overestimates() {
+    // manhattan ignores diagonal moves, open ground is where that shows
+    let mut grid = Grid::new(12, 12);
+    let report = grid.check_heuristic(&Heuristic::Manhattan, (10, 9));
+    assert!(!report.is_admissible());
+    assert!(!report.is_consistent());
+    let overestimate = &report.inadmissible[0];
+    assert!(overestimate.estimate > overestimate.distance);
+    let (x, y) = (overestimate.pos.x as usize, overestimate.pos.y as usize);
+    assert!(report.overestimate_at(x, y).is_some_and(|by| by > 0));
+    grid.allow_diagonal = false;
+    let report = grid.check_heuristic(&Heuristic::Manhattan, (10, 9));
+    assert!(report.is_admissible() && report.is_consistent());
+
+    let grid = Grid::noise_terrain(12, 12, 4.0, 6);
+    for heuristic in [
+        Heuristic::Diagonal,
+        Heuristic::Euclidean,
+        Heuristic::Landmark,
+    ] {
+        let report = grid.check_heuristic(&heuristic, (10, 9));
+        assert!(report.is_admissible(), "{heuristic:?}");
+        assert!(report.is_consistent(), "{heuristic:?}");
+    }
+}
+
+#[test]
+fn generic_costs_price_diagonals() {
+    use crate::cost::{Fixed, OrderedFloat};
+    let mut grid = Grid::<OrderedFloat>::with_cost_type(6, 6);
+    grid.set_start(0, 0);
+    grid.set_goal(4, 5);
+    grid.set_terrain_cost(2, 2, 3);
+    assert_eq!(SolveResult::Found, grid.solve(&Heuristic::Euclidean));
+    let expected = 4.0 * std::f64::consts::SQRT_2 + 1.0;
+    assert!((grid.get_node_at(4, 5).g_cost.0 - expected).abs() < 1e-9);
+    assert_eq!(SolveResult::Found, grid.solve(&Heuristic::Landmark));
+    assert!((grid.get_node_at(4, 5).g_cost.0 - expected).abs() < 1e-9);
+
+    let mut grid = Grid::<Fixed>::with_cost_type(6, 6);
+    grid.set_start(0, 0);
+    grid.set_goal(4, 5);
+    assert_eq!(SolveResult::Found, grid.solve(&Heuristic::Diagonal));
+    assert!((grid.get_node_at(4, 5).g_cost.to_f64() - expected).abs() < 1e-3);
+
+    let mut grid = Grid::<u64>::with_cost_type(6, 6);
+    grid.set_start(0, 0);
+    grid.set_goal(4, 5);
+    assert_eq!(SolveResult::Found, grid.solve(&Heuristic::Diagonal));
+    assert_eq!(4 * 14 + 10, grid.get_node_at(4, 5).g_cost);
+}
+
+#[test]
+fn generic_costs_saturate() {
+    use crate::cost::{Cost, Fixed, OrderedFloat};
+    assert_eq!(u16::MAX, 10u16.times(70_000));
+    assert_eq!(u16::MAX, 1_000u16.times(100));
+    assert_eq!(Fixed::infinity(), Fixed::ONE.times(usize::MAX));
+    assert_eq!(-40i32, (-10i32).times(4));
+
+    // a step too costly for u16 can't be taken, so the path goes around it
+    let mut grid = Grid::<u16>::parse_with_cost_type("S.G\n...").unwrap();
+    grid.set_terrain_cost(1, 0, 10_000);
+    assert_eq!(SolveResult::Found, grid.solve(&Heuristic::Diagonal));
+    assert_eq!(28, grid.get_node_at(2, 0).g_cost);
+
+    // sums past what u16 can hold don't overflow, and paths costing that
+    // much are no paths rather than a goal found without a path to it
+    let mut maze = Grid::<u16>::maze_with_cost_type(301, 301, &crate::maze::MazeAlgorithm::Prim, 3);
+    maze.set_start(1, 1);
+    maze.set_goal(299, 299);
+    assert_eq!(SolveResult::Found, maze.solve(&Heuristic::Manhattan));
+    assert!(maze.path().is_some());
+    assert!(maze.get_node_at(299, 299).g_cost < u16::MAX);
+    let mut line: Grid<u16> = Grid::with_cost_type(8000, 1);
+    line.set_start(0, 0);
+    line.set_goal(0, 7999);
+    assert_eq!(SolveResult::Unreachable, line.solve(&Heuristic::Euclidean));
+    assert_eq!(None, line.path());
+    line.set_goal(0, 6000);
+    assert_eq!(SolveResult::Found, line.solve(&Heuristic::Euclidean));
+    assert_eq!(60_000, line.get_node_at(0, 6000).g_cost);
+
+    let grid = Grid::<OrderedFloat>::parse_with_cost_type("S.G\n.#.").unwrap();
+    assert_eq!("S.G\n.#.\n", grid.to_string());
+    let field = grid.flow_field(&[(2, 0)]);
+    assert_eq!(Some(OrderedFloat(2.0)), field.cost_at(0, 0));
+    assert_eq!(
+        Some(OrderedFloat(1.0 + std::f64::consts::SQRT_2)),
+        field.cost_at(0, 1)
+    );
+}
+
+#[test]
+fn world_loads_chunks_lazily() {
+    use crate::limits::SearchLimits;
+    use crate::world::{Cell, World};
+    let mut world = World::new();
+    assert_eq!(0, world.loaded_chunks());
+    world.set_obstacle(-1, -1);
+    assert_eq!(Cell::WALL, world.cell_at(-1, -1));
+    assert_eq!(Cell::OPEN, world.cell_at(-64, -64));
+    assert_eq!(1, world.loaded_chunks());
+
+    let search = world.solve(
+        (-100, -5),
+        (70, 3),
+        &Heuristic::Diagonal,
+        &SearchLimits::default(),
+    );
+    assert_eq!(SolveResult::Found, search.result);
+    assert_eq!(8 * 14 + 162 * 10, search.cost);
+    assert_eq!(Some(&Position::new(-100, -5)), search.path.first());
+    assert_eq!(Some(&Position::new(70, 3)), search.path.last());
+    assert!(world.loaded_chunks() < 20);
+}
+
+#[test]
+fn world_generator_shapes_the_map() {
+    use crate::limits::{SearchLimits, StopReason};
+    use crate::world::{Cell, World};
+    // a wall along x = 10 with a single gap far to the north
+    let world = World::with_generator(|x, y| {
+        if x == 10 && y != -90 {
+            Cell::WALL
+        } else {
+            Cell::OPEN
+        }
+    });
+    let search = world.solve(
+        (0, 0),
+        (20, 0),
+        &Heuristic::Euclidean,
+        &SearchLimits::default(),
+    );
+    assert_eq!(SolveResult::Found, search.result);
+    assert!(search.path.contains(&Position::new(10, -90)));
+
+    let limits = SearchLimits {
+        max_expanded: Some(50),
+        ..Default::default()
+    };
+    let search = world.solve((0, 0), (20, 0), &Heuristic::Euclidean, &limits);
+    assert_eq!(SolveResult::Partial(StopReason::NodeBudget), search.result);
+    assert_eq!(Some(&Position::new(0, 0)), search.path.first());
+
+    // a closed ring of walls around the start
+    let world = World::with_generator(|x: i32, y: i32| {
+        if x.abs().max(y.abs()) == 5 {
+            Cell::WALL
+        } else {
+            Cell::OPEN
+        }
+    });
+    let search = world.solve(
+        (0, 0),
+        (9, 9),
+        &Heuristic::Diagonal,
+        &SearchLimits::default(),
+    );
+    assert_eq!(SolveResult::Unreachable, search.result);
+    assert!(search.path.is_empty());
+
+    // a walled-in goal on an endless plain, the default node budget ends the search
+    let mut world = World::new();
+    for (dx, dy) in [
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+        (-1, 0),
+        (1, 0),
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+    ] {
+        world.set_obstacle(30 + dx, dy);
+    }
+    let search = world.solve(
+        (0, 0),
+        (30, 0),
+        &Heuristic::Diagonal,
+        &SearchLimits::default(),
+    );
+    assert_eq!(SolveResult::Partial(StopReason::NodeBudget), search.result);
+    assert_eq!(Some(&Position::new(0, 0)), search.path.first());
+}
+
+#[test]
+fn obstacles_can_be_cleared() {
+    let mut grid: Grid = "S#G".parse().unwrap();
+    assert!(!grid.is_goal_reachable());
+    grid.clear_obstacle(1, 0);
+    assert!(!grid.is_obstacle_at(1, 0));
+    assert!(grid.is_goal_reachable());
+    grid.toggle_obstacle(1, 0);
+    assert!(!grid.is_goal_reachable());
+    grid.toggle_obstacle(1, 0);
+    assert_eq!(SolveResult::Found, grid.solve(&Heuristic::Manhattan));
+
+    // edits off the grid are ignored
+    grid.set_obstacle(5, 0);
+    grid.clear_obstacle(5, 0);
+    grid.toggle_obstacle(0, 5);
+    assert_eq!("S*G\n", grid.to_string());
+    assert!(grid.is_goal_reachable());
+
+    grid.clear_start();
+    grid.clear_goal();
+    assert!(!grid.is_ready());
+}
+
+#[test]
+fn bulk_edits() {
+    let mut grid = Grid::new(6, 8);
+    grid.fill_rect(1, 1, 3, 2, true);
+    grid.fill_rect(6, 4, 5, 5, true);
+    grid.draw_line((0, 5), (7, 0), true);
+    grid.set_terrain_cost(0, 0, 4);
+    let expected = "\
+        .......#
+        .###.##.
+        .####...
+        ...#....
+        .##...##
+        #.....##
+    ";
+    let expected: Grid = expected.parse().unwrap();
+    assert_eq!(expected.to_string(), grid.to_string().replace('4', "."));
+
+    // paint bucket the open region in the top left corner
+    grid.flood_fill(0, 0, true);
+    assert!(grid.is_obstacle_at(0, 0) && grid.is_obstacle_at(0, 4));
+    assert!(!grid.is_obstacle_at(7, 1) && !grid.is_obstacle_at(4, 3));
+    // which now touches the walls, so clearing it takes them too
+    grid.flood_fill(0, 0, false);
+    assert!(!grid.is_obstacle_at(0, 0) && !grid.is_obstacle_at(1, 1));
+    assert!(grid.is_obstacle_at(6, 4));
+
+    grid.set_start(0, 0);
+    grid.clear_all();
+    assert!(grid.start().is_none());
+    assert_eq!(Grid::new(6, 8).to_string(), grid.to_string());
+}
+
+#[test]
+fn transforms_keep_contents() {
+    use crate::transform::Anchor;
+    let grid: Grid = "
+        S#.
+        .3G
+    "
+    .parse()
+    .unwrap();
+    let check = |grid: Grid, expected: &str| {
+        let expected: Grid = expected.parse().unwrap();
+        assert_eq!(expected.to_string(), grid.to_string());
+        assert_eq!(expected.start(), grid.start());
+        assert_eq!(expected.goal(), grid.goal());
+    };
+    check(grid.rotate_clockwise(), ".S\n3#\nG.");
+    check(grid.rotate_counter_clockwise(), ".G\n#3\nS.");
+    check(grid.flip_horizontal(), ".#S\nG3.");
+    check(grid.flip_vertical(), ".3G\nS#.");
+    check(grid.rotate_clockwise().rotate_clockwise(), "G3.\n.#S");
+    check(
+        grid.resize(3, 5, Anchor::BottomRight),
+        ".....\n..S#.\n...3G",
+    );
+    check(grid.resize(1, 2, Anchor::TopLeft), "S#");
+    check(grid.crop(1, 0, 2, 2), "#.\n3G");
+    check(grid.tile(2, 2), "S#..#.\n.3G.3.\n.#..#.\n.3..3.");
+    check(
+        grid.concat_horizontal(&"#\n.\n#".parse().unwrap()),
+        "S#.#\n.3G.\n...#",
+    );
+    check(
+        grid.crop(2, 0, 1, 2).concat_vertical(&grid),
+        "...\nG..\nS#.\n.3.",
+    );
+}
+
+#[test]
+fn supercover_lines_include_corners() {
+    use crate::visibility::supercover_line;
+    let line = supercover_line(&Position::new(0, 0), &Position::new(2, 2));
+    let expected = [(0, 0), (1, 0), (0, 1), (1, 1), (2, 1), (1, 2), (2, 2)];
+    assert_eq!(expected.map(|(x, y)| Position::new(x, y)).to_vec(), line);
+    let line = supercover_line(&Position::new(3, 1), &Position::new(0, 0));
+    // passes through the corner between (1, 1) and (2, 0)
+    let expected = [(3, 1), (2, 1), (1, 1), (2, 0), (1, 0), (0, 0)];
+    assert_eq!(expected.map(|(x, y)| Position::new(x, y)).to_vec(), line);
+}
+
+#[test]
+fn line_of_sight_and_raycasts() {
+    let grid: Grid = "
+        ......
+        ..#...
+        ......
+        ....#.
+    "
+    .parse()
+    .unwrap();
+    assert!(grid.has_line_of_sight((0, 0), (5, 0)));
+    assert!(!grid.has_line_of_sight((0, 1), (5, 1)));
+    // the line from (1, 0) to (3, 2) grazes the corners of the obstacle
+    assert!(!grid.has_line_of_sight((1, 0), (3, 2)));
+    assert!(grid.has_line_of_sight((0, 3), (4, 3)));
+
+    let hit = grid.raycast((0.5, 1.5), (1.0, 0.0), 10.0).unwrap();
+    assert_eq!(Position::new(2, 1), hit.node);
+    assert!((hit.distance - 1.5).abs() < 1e-5);
+    assert!((hit.point.0 - 2.0).abs() < 1e-5);
+    assert_eq!(None, grid.raycast((0.5, 1.5), (1.0, 0.0), 1.0));
+    assert_eq!(None, grid.raycast((0.5, 0.5), (1.0, 0.0), 10.0));
+    let hit = grid.raycast((0.5, 3.5), (1.0, -0.1), 10.0).unwrap();
+    assert_eq!(Position::new(4, 3), hit.node);
+}
+
+#[test]
+fn field_of_view_casts_shadows() {
+    let grid: Grid = "
+        .......
+        .......
+        ...#...
+        .......
+        .......
+    "
+    .parse()
+    .unwrap();
+    let visible = grid.field_of_view(3, 4, 10);
+    assert!(visible.contains(&Position::new(3, 4)));
+    assert!(visible.contains(&Position::new(3, 2)));
+    assert!(!visible.contains(&Position::new(3, 1)));
+    assert!(!visible.contains(&Position::new(3, 0)));
+    assert!(visible.contains(&Position::new(0, 0)));
+    assert!(visible.contains(&Position::new(6, 4)));
+
+    let visible = grid.field_of_view(0, 0, 2);
+    assert!(visible.contains(&Position::new(2, 0)));
+    assert!(!visible.contains(&Position::new(3, 0)));
+    assert!(!visible.contains(&Position::new(2, 2)));
+}
+
+#[test]
+fn navmesh_covers_free_space() {
+    let map = "
+        ........
+        ...##...
+        ...##...
+        ........
+    ";
+    let grid: Grid = map.parse().unwrap();
+    let mesh = grid.navmesh(1);
+    for y in 0..4 {
+        for x in 0..8 {
+            assert_eq!(grid.is_obstacle_at(x, y), mesh.region_at(x, y).is_none());
+        }
+    }
+    for portal in mesh.portals() {
+        let [a, b] = portal.regions;
+        assert!(mesh.region(a).is_some() && mesh.region(b).is_some());
+    }
+
+    // the pairs of side by side nodes an agent can cross between, inside a
+    // region or through a portal over their shared edge
+    use crate::navmesh::NavMesh;
+    use std::collections::HashSet;
+    type Link = ((usize, usize), (usize, usize));
+    fn links(mesh: &NavMesh) -> HashSet<Link> {
+        let mut links = HashSet::new();
+        for y in 0..4 {
+            for x in 0..8 {
+                let Some(a) = mesh.region_at(x, y) else {
+                    continue;
+                };
+                for (next_x, next_y) in [(x + 1, y), (x, y + 1)] {
+                    let Some(b) = mesh.region_at(next_x, next_y) else {
+                        continue;
+                    };
+                    let (edge_x, edge_y) = (next_x as f32, next_y as f32);
+                    let crossed = a == b
+                        || mesh.portals().iter().any(|portal| {
+                            let [(x0, y0), (x1, y1)] = portal.ends;
+                            let spans = if next_x > x {
+                                x0 == edge_x
+                                    && x1 == edge_x
+                                    && y0.min(y1) <= edge_y
+                                    && edge_y + 1.0 <= y0.max(y1)
+                            } else {
+                                y0 == edge_y
+                                    && y1 == edge_y
+                                    && x0.min(x1) <= edge_x
+                                    && edge_x + 1.0 <= x0.max(x1)
+                            };
+                            portal.regions.contains(&a) && portal.regions.contains(&b) && spans
+                        });
+                    if crossed {
+                        links.insert(((x, y), (next_x, next_y)));
+                    }
+                }
+            }
+        }
+        links
+    }
+
+    // updating after edits gives the same regions and portals as a fresh
+    // mesh, up to how the free space is split into rectangles
+    for agent_size in [1, 2] {
+        let mut grid: Grid = map.parse().unwrap();
+        let mut updated = grid.navmesh(agent_size);
+        grid.set_obstacle(6, 2);
+        updated.update(&grid, 6, 2, 1, 1);
+        grid.clear_obstacle(3, 1);
+        updated.update(&grid, 3, 1, 1, 1);
+        let fresh = grid.navmesh(agent_size);
+        for y in 0..4 {
+            for x in 0..8 {
+                assert_eq!(
+                    fresh.region_at(x, y).is_none(),
+                    updated.region_at(x, y).is_none()
+                );
+                if let Some(id) = updated.region_at(x, y) {
+                    let region = updated.region(id).unwrap();
+                    assert!((region.x..region.x + region.width).contains(&x));
+                    assert!((region.y..region.y + region.height).contains(&y));
+                }
+            }
+        }
+        let pairs: HashSet<[usize; 2]> = updated
+            .portals()
+            .iter()
+            .map(|portal| {
+                let [a, b] = portal.regions;
+                assert!(updated.region(a).is_some() && updated.region(b).is_some());
+                [a.min(b), a.max(b)]
+            })
+            .collect();
+        assert_eq!(updated.portals().len(), pairs.len());
+        assert_eq!(links(&fresh), links(&updated));
+        assert_eq!(
+            fresh.find_path((0.5, 0.5), (7.5, 3.5)).is_some(),
+            updated.find_path((0.5, 0.5), (7.5, 3.5)).is_some()
+        );
+    }
+}
+
+#[test]
+fn navmesh_paths_are_pulled_tight() {
+    let grid: Grid = "
+        ........
+        ...##...
+        ...##...
+        ...##...
+    "
+    .parse()
+    .unwrap();
+    let mesh = grid.navmesh(1);
+    let path = mesh.find_path((0.5, 3.5), (7.5, 3.5)).unwrap();
+    assert_eq!(Some(&(0.5, 3.5)), path.first());
+    assert_eq!(Some(&(7.5, 3.5)), path.last());
+    // over the wall, touching its top corners
+    assert_eq!(vec![(0.5, 3.5), (3.0, 1.0), (5.0, 1.0), (7.5, 3.5)], path);
+
+    // open ground is a straight line
+    let path = mesh.find_path((0.5, 0.5), (7.5, 0.5)).unwrap();
+    assert_eq!(vec![(0.5, 0.5), (7.5, 0.5)], path);
+    assert_eq!(None, mesh.find_path((3.5, 2.5), (0.5, 0.5)));
+}
+
+#[test]
+fn dimacs_graphs_are_routed() {
+    use crate::road::{GraphHeuristic, ParseGraphError, RoadGraph};
+    // a square with a long diagonal shortcut that isn't worth taking
+    let gr = "
+        c four corners
+        p sp 4 6
+        a 1 2 100
+        a 2 3 100
+        a 1 4 100
+        a 4 3 100
+        a 1 3 250
+        a 3 1 1
+    ";
+    let co = "
+        p aux sp co 4
+        v 1 0 0
+        v 2 1000 0
+        v 3 1000 1000
+        v 4 0 1000
+    ";
+    let mut graph = RoadGraph::from_dimacs(gr).unwrap();
+    assert_eq!((4, 6), (graph.node_count(), graph.edge_count()));
+    graph.add_dimacs_coordinates(co).unwrap();
+    assert_eq!(Some((0.001, 0.001)), graph.coordinates(2));
+    for heuristic in [
+        GraphHeuristic::Zero,
+        GraphHeuristic::Euclidean,
+        GraphHeuristic::GreatCircle,
+    ] {
+        let route = graph.solve(0, 2, heuristic).unwrap();
+        assert_eq!(200, route.cost);
+        assert_eq!(3, route.path.len());
+        assert!(graph.estimate(heuristic, 0, 2) <= 200);
+    }
+    assert_eq!(
+        1,
+        graph.solve(2, 0, GraphHeuristic::Euclidean).unwrap().cost
+    );
+    assert_eq!(
+        201,
+        graph.solve(1, 3, GraphHeuristic::Euclidean).unwrap().cost
+    );
+    let one_way = RoadGraph::from_dimacs("p sp 2 1\na 1 2 5").unwrap();
+    assert_eq!(None, one_way.solve(1, 0, GraphHeuristic::Zero));
+
+    assert_eq!(
+        Err(ParseGraphError::MissingProblemLine),
+        RoadGraph::from_dimacs("a 1 2 3").map(|_| ())
+    );
+    assert_eq!(
+        Err(ParseGraphError::NodeOutOfRange { line: 2, node: 3 }),
+        RoadGraph::from_dimacs("p sp 2 1\na 1 3 5").map(|_| ())
+    );
+    assert_eq!(
+        Err(ParseGraphError::BadLine { line: 2 }),
+        RoadGraph::from_dimacs("p sp 2 1\na 1 x 5").map(|_| ())
+    );
+    assert_eq!(
+        Err(ParseGraphError::ArcCount {
+            expected: 2,
+            found: 1
+        }),
+        RoadGraph::from_dimacs("p sp 2 2\na 1 2 5").map(|_| ())
+    );
+}
+
+#[test]
+fn contraction_hierarchy_matches_dijkstra() {
+    use crate::maze::MazeAlgorithm;
+    use crate::road::GraphHeuristic;
+    let mut maze = Grid::maze(15, 15, &MazeAlgorithm::Prim, 7);
+    maze.set_terrain_cost(5, 5, 4);
+    // open ground has many equally short paths
+    let mut field = Grid::new(12, 12);
+    field.fill_rect(3, 2, 1, 8, true);
+    field.fill_rect(5, 6, 6, 1, true);
+    field.set_terrain_cost(8, 3, 3);
+    for grid in [maze, field] {
+        let graph = grid.road_graph();
+        let hierarchy = graph.contraction_hierarchy();
+        let mut context = hierarchy.context();
+        let open: Vec<usize> = (0..graph.node_count())
+            .filter(|&node| !graph.edges(node).is_empty())
+            .collect();
+        for (i, &start) in open.iter().enumerate().step_by(7) {
+            let goal = open[(i * 31 + 5) % open.len()];
+            let expected = graph.solve(start, goal, GraphHeuristic::Euclidean);
+            let route = hierarchy.query_with(&mut context, start, goal);
+            assert_eq!(expected.is_some(), route.is_some());
+            let (Some(expected), Some(route)) = (expected, route) else {
+                continue;
+            };
+            assert_eq!(expected.cost, route.cost);
+            // the unpacked path only uses arcs of the original graph
+            assert_eq!(Some(&start), route.path.first());
+            assert_eq!(Some(&goal), route.path.last());
+            let cost: u64 = route
+                .path
+                .windows(2)
+                .map(|pair| {
+                    let edge = graph.edges(pair[0]).iter().find(|edge| edge.to == pair[1]);
+                    edge.expect("arc is in the graph").weight
+                })
+                .sum();
+            assert_eq!(route.cost, cost);
+        }
+    }
+    // a wall splits the grid in two
+    let mut grid = Grid::new(3, 3);
+    grid.fill_rect(1, 0, 1, 3, true);
+    let hierarchy = grid.road_graph().contraction_hierarchy();
+    assert_eq!(None, hierarchy.query(0, 2));
+    assert_eq!(
+        Some(vec![3, 0]),
+        hierarchy.query(3, 0).map(|route| route.path)
+    );
+    assert_eq!(
+        Some(vec![Position::new(0, 1), Position::new(0, 0)]),
+        grid.hierarchy_path(&hierarchy, (0, 1), (0, 0))
+    );
+    assert_eq!(None, grid.hierarchy_path(&hierarchy, (0, 0), (2, 0)));
+
+    // hierarchies can be shared between threads
+    fn is_sync<T: Sync>() {}
+    is_sync::<crate::contraction::ContractionHierarchy>();
+}
+
+#[test]
+fn yen_finds_k_shortest_paths() {
+    let mut grid: Grid = "
+        S...
+        .#..
+        ...G
+    "
+    .parse()
+    .unwrap();
+    grid.allow_diagonal = false;
+    // every loopless path, found by brute force
+    fn walk(grid: &Grid, path: &mut Vec<Position>, lengths: &mut Vec<usize>) {
+        let pos = path[path.len() - 1].clone();
+        if pos == Position::new(3, 2) {
+            lengths.push(path.len() - 1);
+            return;
+        }
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let next = Position::new(pos.x + dx, pos.y + dy);
+            let inside = (0..4).contains(&next.x) && (0..3).contains(&next.y);
+            if inside
+                && !grid.is_obstacle_at(next.x as usize, next.y as usize)
+                && !path.contains(&next)
+            {
+                path.push(next);
+                walk(grid, path, lengths);
+                path.pop();
+            }
+        }
+    }
+    let mut lengths = Vec::new();
+    walk(&grid, &mut vec![Position::new(0, 0)], &mut lengths);
+    lengths.sort();
+
+    let paths = grid.k_shortest_paths(8, &Heuristic::Manhattan);
+    let costs: Vec<usize> = paths.iter().map(|path| path.cost).collect();
+    let expected: Vec<usize> = lengths.iter().take(8).map(|steps| steps * 10).collect();
+    assert_eq!(expected, costs);
+    for (i, alternative) in paths.iter().enumerate() {
+        assert_eq!(Some(&Position::new(0, 0)), alternative.path.first());
+        assert_eq!(Some(&Position::new(3, 2)), alternative.path.last());
+        let unique: std::collections::HashSet<_> = alternative.path.iter().collect();
+        assert_eq!(alternative.path.len(), unique.len());
+        assert!(paths[..i]
+            .iter()
+            .all(|other| other.path != alternative.path));
+    }
+    // asking for more paths than there are returns all of them
+    let all = grid.k_shortest_paths(100, &Heuristic::Manhattan);
+    assert_eq!(lengths.len(), all.len());
+    assert!(grid.k_shortest_paths(0, &Heuristic::Manhattan).is_empty());
+}
+
+#[test]
+fn alternative_routes_are_distinct() {
+    use crate::alternatives::AlternativeSettings;
+    let grid: Grid = "
+        .........
+        .###.###.
+        S.......G
+        .###.###.
+        .........
+    "
+    .parse()
+    .unwrap();
+    let settings = AlternativeSettings {
+        max_overlap: 0.6,
+        ..AlternativeSettings::default()
+    };
+    let routes = grid.alternative_routes(&settings, &Heuristic::Euclidean);
+    assert!(routes.len() > 1 && routes.len() <= settings.count);
+    // the first route is the shortest path
+    assert_eq!(80, routes[0].cost);
+    for (i, route) in routes.iter().enumerate() {
+        for other in &routes[..i] {
+            let shared = route.path.iter().filter(|pos| other.path.contains(pos));
+            assert!(shared.count() as f32 <= 0.6 * route.path.len() as f32);
+        }
+    }
+}
+
+#[test]
+fn waypoints_are_visited_in_order() {
+    let mut grid: Grid = "
+        S.....
+        ..#...
+        ..#..G
+    "
+    .parse()
+    .unwrap();
+    let route = grid
+        .solve_waypoints(&[(5, 0), (0, 2)], &Heuristic::Manhattan)
+        .unwrap();
+    assert_eq!(vec![0, 1], route.order);
+    assert_eq!(3, route.legs.len());
+    assert_eq!(vec![50, 58, 66], route.legs);
+    assert_eq!(174, route.cost);
+    assert_eq!(Some(&Position::new(0, 0)), route.path.first());
+    assert_eq!(Some(&Position::new(5, 2)), route.path.last());
+    assert!(route.path.contains(&Position::new(5, 0)));
+    assert!(route
+        .path
+        .windows(2)
+        .all(|pair| { (pair[0].x - pair[1].x).abs() <= 1 && (pair[0].y - pair[1].y).abs() <= 1 }));
+    // the start and goal are put back, and the goal isn't drawn as path
+    assert!(grid.is_start(0, 0) && grid.is_goal(5, 2));
+    assert!(grid.get_node_at(5, 2).node_type != NodeType::Path);
+    assert!(grid.get_node_at(5, 0).node_type == NodeType::Path);
+
+    // a waypoint on the start costs nothing
+    let route = grid
+        .solve_waypoints(&[(0, 0)], &Heuristic::Manhattan)
+        .unwrap();
+    assert_eq!(Position::new(0, 0), route.path[0]);
+    assert_eq!(0, route.legs[0]);
+
+    grid.fill_rect(4, 1, 2, 1, true);
+    grid.fill_rect(3, 0, 1, 2, true);
+    assert_eq!(None, grid.solve_waypoints(&[(5, 0)], &Heuristic::Manhattan));
+}
+
+#[test]
+fn waypoint_order_is_optimised() {
+    let mut grid = Grid::new(8, 8);
+    grid.set_start(0, 0);
+    grid.set_goal(7, 7);
+    let waypoints = [(6, 6), (1, 1), (4, 4), (2, 2)];
+    let route = grid
+        .solve_waypoints_any_order(&waypoints, &Heuristic::Diagonal)
+        .unwrap();
+    // all on the diagonal, so visited from closest to furthest
+    assert_eq!(vec![1, 3, 2, 0], route.order);
+    assert_eq!(7 * 14, route.cost);
+    let in_order = grid
+        .solve_waypoints(&waypoints, &Heuristic::Diagonal)
+        .unwrap();
+    assert!(in_order.cost > route.cost);
+
+    // too many waypoints for the exact search still finds the diagonal
+    let waypoints: Vec<(usize, usize)> = (1..7).rev().chain(1..7).map(|i| (i, i)).collect();
+    assert!(waypoints.len() > crate::waypoints::EXACT_WAYPOINT_LIMIT);
+    let route = grid
+        .solve_waypoints_any_order(&waypoints, &Heuristic::Diagonal)
+        .unwrap();
+    assert_eq!(7 * 14, route.cost);
+    assert_eq!(waypoints.len() + 1, route.legs.len());
+
+    let route = grid
+        .solve_waypoints_any_order(&[], &Heuristic::Diagonal)
+        .unwrap();
+    assert_eq!((vec![], 7 * 14), (route.order, route.cost));
+
+    // a walled in waypoint can't be visited in any order
+    grid.fill_rect(0, 6, 2, 1, true);
+    grid.set_obstacle(1, 7);
+    let mut waypoints = waypoints;
+    waypoints.push((0, 7));
+    assert_eq!(
+        None,
+        grid.solve_waypoints_any_order(&waypoints, &Heuristic::Diagonal)
+    );
+    assert!(grid.path().is_none());
+}
@@ -0,0 +1,166 @@
+use crate::cost::Cost;
+use crate::node::NodeType;
+use crate::position::Position;
+use crate::Grid;
+use std::collections::HashMap;
+
+// which part of the old grid stays in place when resizing
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    // where the old grid starts inside the new one along each axis
+    fn offset(self, old: (usize, usize), new: (usize, usize)) -> (i32, i32) {
+        let (old_width, old_height) = (old.0 as i32, old.1 as i32);
+        let (new_width, new_height) = (new.0 as i32, new.1 as i32);
+        let x = match self {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => 0,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => (new_width - old_width) / 2,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => new_width - old_width,
+        };
+        let y = match self {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => 0,
+            Anchor::Left | Anchor::Center | Anchor::Right => (new_height - old_height) / 2,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => new_height - old_height,
+        };
+        (x, y)
+    }
+}
+
+impl<C: Cost> Grid<C> {
+    // builds a grid of the given size where every node copies the node of this
+    // grid that `source` points it at, or stays empty when that is outside. the start, goal and
+    // reservations follow the first node they were copied to
+    fn remap(&self, height: usize, width: usize, source: impl Fn(i32, i32) -> Position) -> Grid<C> {
+        let mut grid = Grid::with_cost_type(height, width);
+        grid.allow_diagonal = self.allow_diagonal;
+        grid.landmark_count = self.landmark_count;
+        let mut moved: HashMap<Position, Position> = HashMap::new();
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let old = source(x, y);
+                if !self.is_valid_pos(&old) {
+                    continue;
+                }
+                let new = Position::new(x, y);
+                grid.copy_node(&new, self, &old);
+                moved.entry(old).or_insert(new);
+            }
+        }
+        grid.start = self.start.as_ref().and_then(|pos| moved.get(pos).cloned());
+        grid.goal = self.goal.as_ref().and_then(|pos| moved.get(pos).cloned());
+        grid.reservations = self
+            .reservations
+            .iter()
+            .filter_map(|(pos, time)| Some((moved.get(pos)?.clone(), *time)))
+            .collect();
+        grid
+    }
+
+    fn copy_node(&mut self, to: &Position, from_grid: &Grid<C>, from: &Position) {
+        let from = from_grid.nodes[from].borrow();
+        let mut node = self.nodes[to].borrow_mut();
+        node.terrain_cost = from.terrain_cost;
+        if from.node_type == NodeType::Obstacle {
+            node.set_obstacle();
+        }
+    }
+
+    // a grid of the new size with the contents kept in place at `anchor`,
+    // anything falling outside is cut off
+    pub fn resize(&self, height: usize, width: usize, anchor: Anchor) -> Grid<C> {
+        let (dx, dy) = anchor.offset((self.width, self.height), (width, height));
+        self.remap(height, width, |x, y| Position::new(x - dx, y - dy))
+    }
+
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Grid<C> {
+        assert!(
+            x + width <= self.width && y + height <= self.height,
+            "crop is outside the grid"
+        );
+        let (dx, dy) = (x as i32, y as i32);
+        self.remap(height, width, |x, y| Position::new(x + dx, y + dy))
+    }
+
+    pub fn rotate_clockwise(&self) -> Grid<C> {
+        let last_row = self.height as i32 - 1;
+        self.remap(self.width, self.height, |x, y| {
+            Position::new(y, last_row - x)
+        })
+    }
+
+    pub fn rotate_counter_clockwise(&self) -> Grid<C> {
+        let last_column = self.width as i32 - 1;
+        self.remap(self.width, self.height, |x, y| {
+            Position::new(last_column - y, x)
+        })
+    }
+
+    // mirrors left to right
+    pub fn flip_horizontal(&self) -> Grid<C> {
+        let last_column = self.width as i32 - 1;
+        self.remap(self.height, self.width, |x, y| {
+            Position::new(last_column - x, y)
+        })
+    }
+
+    // mirrors top to bottom
+    pub fn flip_vertical(&self) -> Grid<C> {
+        let last_row = self.height as i32 - 1;
+        self.remap(self.height, self.width, |x, y| {
+            Position::new(x, last_row - y)
+        })
+    }
+
+    // repeats the grid, the start and goal stay in the top left copy
+    pub fn tile(&self, rows: usize, columns: usize) -> Grid<C> {
+        let (width, height) = (self.width as i32, self.height as i32);
+        self.remap(self.height * rows, self.width * columns, |x, y| {
+            Position::new(x % width, y % height)
+        })
+    }
+
+    // puts `other` to the right of this grid. the start and goal come from this
+    // grid, or from `other` when this one has none
+    pub fn concat_horizontal(&self, other: &Grid<C>) -> Grid<C> {
+        let height = self.height.max(other.height);
+        let mut grid = self.resize(height, self.width + other.width, Anchor::TopLeft);
+        grid.paste(other, self.width, 0);
+        grid
+    }
+
+    // puts `other` below this grid, see `concat_horizontal`
+    pub fn concat_vertical(&self, other: &Grid<C>) -> Grid<C> {
+        let width = self.width.max(other.width);
+        let mut grid = self.resize(self.height + other.height, width, Anchor::TopLeft);
+        grid.paste(other, 0, self.height);
+        grid
+    }
+
+    fn paste(&mut self, other: &Grid<C>, left: usize, top: usize) {
+        let (left, top) = (left as i32, top as i32);
+        let pasted = other.remap(self.height, self.width, |x, y| {
+            Position::new(x - left, y - top)
+        });
+        for y in 0..other.height as i32 {
+            for x in 0..other.width as i32 {
+                let pos = Position::new(x + left, y + top);
+                self.copy_node(&pos, &pasted, &pos);
+            }
+        }
+        self.start = self.start.take().or(pasted.start);
+        self.goal = self.goal.take().or(pasted.goal);
+        self.reservations.extend(pasted.reservations);
+    }
+}
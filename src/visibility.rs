@@ -0,0 +1,198 @@
+use crate::cost::Cost;
+use crate::position::Position;
+use crate::Grid;
+
+// every node a straight line between two node centres passes through. where
+// the line goes exactly through a corner both nodes beside it are included
+pub fn supercover_line(from: &Position, to: &Position) -> Vec<Position> {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let (steps_x, steps_y) = (dx.abs(), dy.abs());
+    let (sign_x, sign_y) = (dx.signum(), dy.signum());
+    let mut pos = from.clone();
+    let mut line = vec![pos.clone()];
+    let (mut x, mut y) = (0, 0);
+    while x < steps_x || y < steps_y {
+        // which side of the line the next corner is on
+        let decision = (1 + 2 * x) * steps_y - (1 + 2 * y) * steps_x;
+        if decision == 0 {
+            line.push(Position::new(pos.x + sign_x, pos.y));
+            line.push(Position::new(pos.x, pos.y + sign_y));
+            pos = Position::new(pos.x + sign_x, pos.y + sign_y);
+            x += 1;
+            y += 1;
+        } else if decision < 0 {
+            pos.x += sign_x;
+            x += 1;
+        } else {
+            pos.y += sign_y;
+            y += 1;
+        }
+        line.push(pos.clone());
+    }
+    line
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RayHit {
+    // the obstacle the ray stopped at
+    pub node: Position,
+    pub distance: f32,
+    // where the ray entered the obstacle, in node units
+    pub point: (f32, f32),
+}
+
+// octant transforms for shadowcasting: (xx, xy, yx, yy)
+const OCTANTS: [(i32, i32, i32, i32); 8] = [
+    (1, 0, 0, 1),
+    (0, 1, 1, 0),
+    (0, -1, 1, 0),
+    (-1, 0, 0, 1),
+    (-1, 0, 0, -1),
+    (0, -1, -1, 0),
+    (0, 1, -1, 0),
+    (1, 0, 0, -1),
+];
+
+impl<C: Cost> Grid<C> {
+    // whether nothing blocks the straight line between the two node centres,
+    // the end nodes themselves may be obstacles
+    pub fn has_line_of_sight(&self, from: (usize, usize), to: (usize, usize)) -> bool {
+        let from = Position::new(from.0 as i32, from.1 as i32);
+        let to = Position::new(to.0 as i32, to.1 as i32);
+        assert!(self.is_valid_pos(&from), "{:?} is invalid", from);
+        assert!(self.is_valid_pos(&to), "{:?} is invalid", to);
+        supercover_line(&from, &to)
+            .iter()
+            .filter(|pos| **pos != from && **pos != to)
+            .all(|pos| !self.is_obstacle(pos))
+    }
+
+    // walks a ray through the grid (Amanatides and Woo) and returns the first
+    // obstacle it enters. positions are in node units where node (x, y) covers
+    // [x, x + 1) x [y, y + 1). None when it leaves the grid or runs out of length
+    pub fn raycast(
+        &self,
+        origin: (f32, f32),
+        direction: (f32, f32),
+        max_distance: f32,
+    ) -> Option<RayHit> {
+        let length = direction.0.hypot(direction.1);
+        if length == 0.0 {
+            return None;
+        }
+        let (dx, dy) = (direction.0 / length, direction.1 / length);
+        let mut node = Position::new(origin.0.floor() as i32, origin.1.floor() as i32);
+        // distance along the ray to the next vertical and horizontal node edge
+        let first_edge = |origin: f32, node: i32, d: f32| {
+            if d > 0.0 {
+                (node as f32 + 1.0 - origin) / d
+            } else if d < 0.0 {
+                (origin - node as f32) / -d
+            } else {
+                f32::INFINITY
+            }
+        };
+        let mut next_x = first_edge(origin.0, node.x, dx);
+        let mut next_y = first_edge(origin.1, node.y, dy);
+        let (delta_x, delta_y) = (1.0 / dx.abs(), 1.0 / dy.abs());
+        let mut distance = 0.0;
+        while distance <= max_distance && self.is_valid_pos(&node) {
+            if self.is_obstacle(&node) {
+                return Some(RayHit {
+                    node,
+                    distance,
+                    point: (origin.0 + dx * distance, origin.1 + dy * distance),
+                });
+            }
+            if next_x < next_y {
+                distance = next_x;
+                next_x += delta_x;
+                node.x += dx.signum() as i32;
+            } else {
+                distance = next_y;
+                next_y += delta_y;
+                node.y += dy.signum() as i32;
+            }
+        }
+        None
+    }
+
+    // every node visible from (x, y) within `radius`, using recursive
+    // shadowcasting. obstacles that can be seen are included
+    pub fn field_of_view(&self, x: usize, y: usize, radius: usize) -> Vec<Position> {
+        let origin = Position::new(x as i32, y as i32);
+        assert!(self.is_valid_pos(&origin), "{:?} is invalid", origin);
+        let mut visible = vec![false; self.width * self.height];
+        visible[self.get_index_from_pos(&origin)] = true;
+        for octant in OCTANTS {
+            self.cast_light(&origin, 1, 1.0, 0.0, radius as i32, octant, &mut visible);
+        }
+        (0..visible.len())
+            .filter(|&index| visible[index])
+            .map(|index| self.get_pos_from_index(index))
+            .collect()
+    }
+
+    // lights one octant row by row between the `start` and `end` slopes,
+    // splitting the scan whenever an obstacle casts a shadow
+    #[allow(clippy::too_many_arguments)]
+    fn cast_light(
+        &self,
+        origin: &Position,
+        row: i32,
+        mut start: f32,
+        end: f32,
+        radius: i32,
+        (xx, xy, yx, yy): (i32, i32, i32, i32),
+        visible: &mut [bool],
+    ) {
+        if start < end {
+            return;
+        }
+        let mut next_start = start;
+        for distance in row..=radius {
+            let mut blocked = false;
+            let dy = -distance;
+            for dx in -distance..=0 {
+                let pos = Position::new(origin.x + dx * xx + dy * xy, origin.y + dx * yx + dy * yy);
+                let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+                let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+                if start < right_slope {
+                    continue;
+                }
+                if end > left_slope {
+                    break;
+                }
+                let inside = self.is_valid_pos(&pos);
+                if inside && dx * dx + dy * dy <= radius * radius {
+                    visible[self.get_index_from_pos(&pos)] = true;
+                }
+                let opaque = !inside || self.is_obstacle(&pos);
+                if blocked {
+                    if opaque {
+                        next_start = right_slope;
+                    } else {
+                        blocked = false;
+                        start = next_start;
+                    }
+                } else if opaque && distance < radius {
+                    blocked = true;
+                    let octant = (xx, xy, yx, yy);
+                    self.cast_light(
+                        origin,
+                        distance + 1,
+                        start,
+                        left_slope,
+                        radius,
+                        octant,
+                        visible,
+                    );
+                    next_start = right_slope;
+                }
+            }
+            if blocked {
+                break;
+            }
+        }
+    }
+}
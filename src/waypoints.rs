@@ -0,0 +1,274 @@
+use crate::cost::Cost;
+use crate::node::NodeType;
+use crate::position::Position;
+use crate::{Grid, Heuristic, SolveResult};
+
+// up to this many waypoints the best order is found exactly (Held-Karp),
+// which takes 2^n * n^2 steps. beyond it a nearest neighbour tour is improved with 2-opt
+pub const EXACT_WAYPOINT_LIMIT: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WaypointRoute<C = usize> {
+    // indices into the waypoints in the order they are visited
+    pub order: Vec<usize>,
+    // from the start through every waypoint to the goal, both ends included
+    pub path: Vec<Position>,
+    // the cost of each leg, one more than there are waypoints
+    pub legs: Vec<C>,
+    pub cost: C,
+}
+
+struct Leg<C> {
+    path: Vec<Position>,
+    cost: C,
+}
+
+// the legs between every pair of stops, None where there is no path or the
+// leg is never needed: back to the start, away from the goal or in place
+struct LegTable<C> {
+    legs: Vec<Vec<Option<Leg<C>>>>,
+}
+
+impl<C: Cost> LegTable<C> {
+    fn cost(&self, from: usize, to: usize) -> C {
+        self.legs[from][to]
+            .as_ref()
+            .map_or(C::infinity(), |leg| leg.cost)
+    }
+
+    // the total cost of visiting the stops in order, infinite when a leg is missing
+    fn tour_cost(&self, stops: &[usize]) -> C {
+        stops.windows(2).fold(C::zero(), |total, pair| {
+            total.saturating_add(self.cost(pair[0], pair[1]))
+        })
+    }
+}
+
+// the cheapest order to visit stops 1..=n between stop 0 and stop n + 1.
+// dp[visited][last] is the cheapest way to leave the start, visit the set and stop at `last`
+fn held_karp<C: Cost>(table: &LegTable<C>, waypoints: usize) -> Option<Vec<usize>> {
+    let goal = waypoints + 1;
+    if waypoints == 0 {
+        return (table.cost(0, goal) != C::infinity()).then(Vec::new);
+    }
+    let full = (1 << waypoints) - 1;
+    let mut dp = vec![vec![C::infinity(); waypoints]; 1 << waypoints];
+    let mut parent = vec![vec![usize::MAX; waypoints]; 1 << waypoints];
+    for last in 0..waypoints {
+        dp[1 << last][last] = table.cost(0, last + 1);
+    }
+    for visited in 1..=full {
+        for last in 0..waypoints {
+            let cost = dp[visited][last];
+            if visited & (1 << last) == 0 || cost == C::infinity() {
+                continue;
+            }
+            for next in (0..waypoints).filter(|next| visited & (1 << next) == 0) {
+                let step = table.cost(last + 1, next + 1);
+                if step == C::infinity() {
+                    continue;
+                }
+                let with_next = visited | (1 << next);
+                let with_step = cost.saturating_add(step);
+                if with_step < dp[with_next][next] {
+                    dp[with_next][next] = with_step;
+                    parent[with_next][next] = last;
+                }
+            }
+        }
+    }
+    let (_, last) = (0..waypoints)
+        .map(|last| {
+            (
+                dp[full][last].saturating_add(table.cost(last + 1, goal)),
+                last,
+            )
+        })
+        .filter(|&(cost, _)| cost != C::infinity())
+        .min()?;
+    let mut order = vec![last];
+    let mut visited = full;
+    while parent[visited][order[order.len() - 1]] != usize::MAX {
+        let last = order[order.len() - 1];
+        visited &= !(1 << last);
+        order.push(parent[visited | (1 << last)][last]);
+    }
+    order.reverse();
+    Some(order)
+}
+
+// greedily visits the closest unvisited stop next, then reverses segments of
+// the tour while that makes it cheaper (2-opt). legs may be asymmetric so
+// every candidate tour is priced in full. None when the tour misses a leg,
+// which proves no order works: grid moves can be walked both ways, so a stop
+// that must be entered and can't be reached from another stop that must be
+// entered (or from the start) is cut off from it whatever the order
+fn nearest_neighbour_two_opt<C: Cost>(table: &LegTable<C>, waypoints: usize) -> Option<Vec<usize>> {
+    let goal = waypoints + 1;
+    let mut tour = vec![0];
+    let mut unvisited: Vec<usize> = (1..=waypoints).collect();
+    while !unvisited.is_empty() {
+        let last = tour[tour.len() - 1];
+        let (index, _) = unvisited
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, &stop)| table.cost(last, stop))?;
+        tour.push(unvisited.swap_remove(index));
+    }
+    tour.push(goal);
+    let mut best = table.tour_cost(&tour);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..waypoints {
+            for j in i + 1..=waypoints {
+                tour[i..=j].reverse();
+                let cost = table.tour_cost(&tour);
+                if cost < best {
+                    best = cost;
+                    improved = true;
+                } else {
+                    tour[i..=j].reverse();
+                }
+            }
+        }
+    }
+    if best == C::infinity() {
+        return None;
+    }
+    Some(tour[1..=waypoints].iter().map(|stop| stop - 1).collect())
+}
+
+impl<C: Cost> Grid<C> {
+    // one leg found by the grid solver, the start and goal are left pointing at it
+    fn solve_leg(
+        &mut self,
+        from: &Position,
+        to: &Position,
+        heuristic: &Heuristic,
+    ) -> Option<Leg<C>> {
+        if from == to {
+            return Some(Leg {
+                path: vec![from.clone()],
+                cost: C::zero(),
+            });
+        }
+        self.start = Some(from.clone());
+        self.goal = Some(to.clone());
+        if self.solve(heuristic) != SolveResult::Found {
+            return None;
+        }
+        // the solver's path runs back from the node before the goal
+        let mut path: Vec<Position> = self.path()?.iter().rev().cloned().collect();
+        path.push(to.clone());
+        let cost = self.nodes[to].borrow().g_cost;
+        Some(Leg { path, cost })
+    }
+
+    // the start, then the waypoints, then the goal
+    fn stops(&self, waypoints: &[(usize, usize)]) -> Option<Vec<Position>> {
+        let mut stops = vec![self.start.clone()?];
+        for &(x, y) in waypoints {
+            let pos = Position::new(x as i32, y as i32);
+            assert!(self.is_valid_pos(&pos), "{:?} is invalid", pos);
+            stops.push(pos);
+        }
+        stops.push(self.goal.clone()?);
+        Some(stops)
+    }
+
+    // solves the legs between consecutive stops and joins them. like `solve`
+    // the joined path is left on the grid, start and goal are restored
+    fn join_legs(
+        &mut self,
+        stops: &[Position],
+        order: Vec<usize>,
+        mut leg: impl FnMut(&mut Self, usize, usize) -> Option<Leg<C>>,
+    ) -> Option<WaypointRoute<C>> {
+        let (start, goal) = (self.start.clone(), self.goal.clone());
+        let visits: Vec<usize> = std::iter::once(0)
+            .chain(order.iter().map(|waypoint| waypoint + 1))
+            .chain(std::iter::once(stops.len() - 1))
+            .collect();
+        let legs: Option<Vec<Leg<C>>> = visits
+            .windows(2)
+            .map(|pair| leg(self, pair[0], pair[1]))
+            .collect();
+        self.start = start;
+        self.goal = goal;
+        self.reset_search();
+        let legs = legs?;
+
+        let mut path = vec![stops[0].clone()];
+        for leg in &legs {
+            path.extend(leg.path[1..].iter().cloned());
+        }
+        // like `solve`, the goal keeps its node type
+        let goal = &stops[stops.len() - 1];
+        for pos in path.iter().filter(|&pos| pos != goal) {
+            self.nodes[pos].borrow_mut().node_type = NodeType::Path;
+        }
+        self.path = Some(path[..path.len() - 1].iter().rev().cloned().collect());
+        let costs: Vec<C> = legs.iter().map(|leg| leg.cost).collect();
+        Some(WaypointRoute {
+            order,
+            path,
+            cost: costs
+                .iter()
+                .fold(C::zero(), |total, &cost| total.saturating_add(cost)),
+            legs: costs,
+        })
+    }
+
+    // visits the waypoints in the given order between the start and the goal.
+    // None without a start or goal, or when some leg can't be walked
+    pub fn solve_waypoints(
+        &mut self,
+        waypoints: &[(usize, usize)],
+        heuristic: &Heuristic,
+    ) -> Option<WaypointRoute<C>> {
+        let stops = self.stops(waypoints)?;
+        let order = (0..waypoints.len()).collect();
+        self.join_legs(&stops, order, |grid, from, to| {
+            grid.solve_leg(&stops[from], &stops[to], heuristic)
+        })
+    }
+
+    // visits every waypoint between the start and the goal in the cheapest
+    // order found. the cost of every leg comes from the grid solver, then the
+    // order is exact up to `EXACT_WAYPOINT_LIMIT` waypoints and heuristic beyond
+    pub fn solve_waypoints_any_order(
+        &mut self,
+        waypoints: &[(usize, usize)],
+        heuristic: &Heuristic,
+    ) -> Option<WaypointRoute<C>> {
+        let stops = self.stops(waypoints)?;
+        let (start, goal) = (self.start.clone(), self.goal.clone());
+        let last = stops.len() - 1;
+        let mut legs = Vec::with_capacity(stops.len());
+        for from in 0..stops.len() {
+            let row = (0..stops.len()).map(|to| {
+                let needed = from != last && to != 0 && from != to;
+                needed
+                    .then(|| self.solve_leg(&stops[from], &stops[to], heuristic))
+                    .flatten()
+            });
+            legs.push(row.collect());
+        }
+        self.start = start;
+        self.goal = goal;
+        let mut table = LegTable { legs };
+
+        let order = if waypoints.len() <= EXACT_WAYPOINT_LIMIT {
+            held_karp(&table, waypoints.len())
+        } else {
+            nearest_neighbour_two_opt(&table, waypoints.len())
+        };
+        let Some(order) = order else {
+            self.reset_search();
+            return None;
+        };
+        self.join_legs(&stops, order, |_, from, to| table.legs[from][to].take())
+    }
+}
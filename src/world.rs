@@ -0,0 +1,226 @@
+use crate::cost::Cost;
+use crate::limits::SearchLimits;
+use crate::position::Position;
+use crate::search::{Search, SearchSpace};
+use crate::{Heuristic, SolveResult};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+// side length of the square chunks the world is loaded in
+pub const CHUNK_SIZE: i32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cell {
+    pub obstacle: bool,
+    // multiplier applied to the cost of stepping onto this cell
+    pub terrain_cost: usize,
+}
+
+impl Cell {
+    pub const OPEN: Cell = Cell {
+        obstacle: false,
+        terrain_cost: 1,
+    };
+    pub const WALL: Cell = Cell {
+        obstacle: true,
+        terrain_cost: 1,
+    };
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell::OPEN
+    }
+}
+
+struct Chunk {
+    cells: Vec<Cell>,
+}
+
+type Generator = Box<dyn Fn(i32, i32) -> Cell>;
+
+// an unbounded map made of chunks that are only allocated once something
+// touches them, new chunks are filled in by the generator
+pub struct World<C = usize> {
+    chunks: RefCell<HashMap<(i32, i32), Chunk>>,
+    generator: Generator,
+    pub allow_diagonal: bool,
+    cost: PhantomData<C>,
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorldPath<C> {
+    pub result: SolveResult,
+    // from the start to the goal, or to the node closest to it for partial
+    // results. empty when the goal is unreachable
+    pub path: Vec<Position>,
+    pub cost: C,
+}
+
+impl World {
+    // an endless open plain
+    pub fn new() -> Self {
+        World::with_generator(|_, _| Cell::OPEN)
+    }
+
+    pub fn with_generator(generator: impl Fn(i32, i32) -> Cell + 'static) -> Self {
+        World::with_cost_type(generator)
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World::new()
+    }
+}
+
+fn chunk_of(x: i32, y: i32) -> ((i32, i32), usize) {
+    let key = (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE));
+    let index = y.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + x.rem_euclid(CHUNK_SIZE);
+    (key, index as usize)
+}
+
+impl<C: Cost> World<C> {
+    pub fn with_cost_type(generator: impl Fn(i32, i32) -> Cell + 'static) -> Self {
+        World {
+            chunks: RefCell::new(HashMap::new()),
+            generator: Box::new(generator),
+            allow_diagonal: true,
+            cost: PhantomData,
+        }
+    }
+
+    fn load_chunk(&self, key: (i32, i32)) {
+        if self.chunks.borrow().contains_key(&key) {
+            return;
+        }
+        let (left, top) = (key.0 * CHUNK_SIZE, key.1 * CHUNK_SIZE);
+        let cells = (0..CHUNK_SIZE * CHUNK_SIZE)
+            .map(|index| (self.generator)(left + index % CHUNK_SIZE, top + index / CHUNK_SIZE))
+            .collect();
+        self.chunks.borrow_mut().insert(key, Chunk { cells });
+    }
+
+    pub fn loaded_chunks(&self) -> usize {
+        self.chunks.borrow().len()
+    }
+
+    pub fn cell_at(&self, x: i32, y: i32) -> Cell {
+        let (key, index) = chunk_of(x, y);
+        self.load_chunk(key);
+        self.chunks.borrow()[&key].cells[index]
+    }
+
+    pub fn set_cell(&mut self, x: i32, y: i32, cell: Cell) {
+        assert!(cell.terrain_cost > 0, "terrain cost must be at least 1");
+        let (key, index) = chunk_of(x, y);
+        self.load_chunk(key);
+        let chunks = self.chunks.get_mut();
+        chunks.get_mut(&key).expect("chunk was just loaded").cells[index] = cell;
+    }
+
+    pub fn set_obstacle(&mut self, x: i32, y: i32) {
+        let cell = self.cell_at(x, y);
+        self.set_cell(
+            x,
+            y,
+            Cell {
+                obstacle: true,
+                ..cell
+            },
+        );
+    }
+
+    pub fn set_terrain_cost(&mut self, x: i32, y: i32, cost: usize) {
+        let cell = self.cell_at(x, y);
+        self.set_cell(
+            x,
+            y,
+            Cell {
+                terrain_cost: cost,
+                ..cell
+            },
+        );
+    }
+
+    // open neighbours with the step cost scaled by their terrain cost, leaving
+    // out steps too costly for `C` to hold
+    fn get_move_costs(&self, me: &Position) -> Vec<(Position, C)> {
+        let straight = [(1, 0), (-1, 0), (0, -1), (0, 1)].map(|offset| (offset, C::straight()));
+        let diagonal = [(1, -1), (-1, -1), (1, 1), (-1, 1)].map(|offset| (offset, C::diagonal()));
+        let diagonal = if self.allow_diagonal {
+            &diagonal[..]
+        } else {
+            &[]
+        };
+        straight
+            .iter()
+            .chain(diagonal)
+            .filter_map(|&((dx, dy), step)| {
+                let cell = self.cell_at(me.x + dx, me.y + dy);
+                let pos = Position::new(me.x + dx, me.y + dy);
+                (!cell.obstacle).then(|| (pos, step.times(cell.terrain_cost)))
+            })
+            .filter(|(_, cost)| *cost != C::infinity())
+            .collect()
+    }
+
+    // A* between two points, loading chunks as the search reaches them. nothing
+    // bounds the world, so a walled-in goal could otherwise be searched for
+    // forever. without a node budget in `limits` the search may expand as many
+    // nodes as a square `CHUNK_SIZE` wider than the start and goal on every side
+    // holds, and returns a partial path once they are spent.
+    // landmark tables need a bounded grid, so `Heuristic::Landmark` estimates
+    // with the euclidean distance here
+    pub fn solve(
+        &self,
+        start: (i32, i32),
+        goal: (i32, i32),
+        heuristic: &Heuristic,
+        limits: &SearchLimits,
+    ) -> WorldPath<C> {
+        let start = Position::new(start.0, start.1);
+        let goal = Position::new(goal.0, goal.1);
+        let unreachable = WorldPath {
+            result: SolveResult::Unreachable,
+            path: Vec::new(),
+            cost: C::infinity(),
+        };
+        if self.cell_at(start.x, start.y).obstacle || self.cell_at(goal.x, goal.y).obstacle {
+            return unreachable;
+        }
+
+        let heuristic = match heuristic {
+            Heuristic::Landmark => Heuristic::Euclidean.geometric(),
+            heuristic => heuristic.geometric(),
+        };
+        let mut limits = limits.clone();
+        if limits.max_expanded.is_none() {
+            let span = (start.x - goal.x).abs().max((start.y - goal.y).abs()) + 2 * CHUNK_SIZE;
+            limits.max_expanded = Some(span as usize * span as usize);
+        }
+        let mut search = Search::new(self, start, goal.clone(), heuristic);
+        match search.run(&limits) {
+            SolveResult::Found => WorldPath {
+                result: SolveResult::Found,
+                path: search.path_to(&goal),
+                cost: search.g_costs[&goal],
+            },
+            SolveResult::Partial(reason) => WorldPath {
+                result: SolveResult::Partial(reason),
+                path: search.path_to(search.best()),
+                cost: search.g_costs[search.best()],
+            },
+            SolveResult::Unreachable => unreachable,
+        }
+    }
+}
+
+impl<C: Cost> SearchSpace<C> for World<C> {
+    fn successors(&self, pos: &Position) -> Vec<(Position, C)> {
+        self.get_move_costs(pos)
+    }
+}